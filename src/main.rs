@@ -1,8 +1,14 @@
-use model::Game;
+use std::io;
 
-mod model;
+use regalis::model::Game;
+use regalis::uci;
 
 fn main() {
+    // Graphical interfaces start the engine with --uci and talk to it over standard input
+    if std::env::args().nth(1).as_deref() == Some("--uci") {
+        uci::run(io::stdin().lock(), &mut io::stdout()).expect("Could not talk UCI.");
+        return;
+    }
     let mut game = Game::new();
     game.run_game();
 }
@@ -1,51 +1,152 @@
-use std::io::{self, Write};
+mod binary;
+mod builder;
+mod castling;
+mod commands;
+mod engine;
+mod eval;
+mod fen;
+mod fuzz;
+mod openings;
+mod perft;
+mod pgn;
+mod san;
+mod search;
+mod zobrist;
+
+pub use self::binary::DecodeError;
+pub use self::builder::{BuildError, GameBuilder};
+pub use self::castling::CastlingRights;
+pub use self::commands::{CommandError, PositionError};
+pub use self::engine::{Engine, MinimaxEngine, RandomEngine};
+pub use self::fen::{fen_diff, validate_fen, FenError};
+pub use self::pgn::PgnError;
+pub use self::search::SearchLimits;
+
+use self::san::piece_from_san_letter;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// Search depth used when no difficulty is chosen
+const DEFAULT_DIFFICULTY: u32 = 3;
 
 /// Standard size of a chess board
 const BOARD_DIMENSIONS: usize = 8;
 
 /// This structure represents the drawn chessboard to be updated after each move
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
-    state: [[Piece; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
+    /// The piece on each square, `None` where a square is empty
+    state: [[Option<Piece>; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
 }
 
 /// This struct represents a game of Chess along with whoever's turn it is
-pub(crate) struct Game {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
     turn: Color,
     board: Board,
+    /// Square a pawn skipped over on the previous move, if it advanced two squares
+    en_passant: Option<Position>,
+    castling: CastlingRights,
+    /// Moves played through make_move, oldest first, so they can be taken back
+    history: Vec<MoveRecord>,
+    /// White's material minus Black's in centipawns, updated as pieces are captured
+    material: i32,
+    /// Moves since the last capture or pawn move, for the fifty-move rule
+    halfmove_clock: u32,
+    /// Number of the current move, starting at 1 and going up after each of Black's moves
+    fullmove_number: u32,
+    /// Zobrist hash of the current position, updated by every move
+    hash: u64,
+    /// Zobrist hash of every position reached through make_move, starting position first
+    position_hashes: Vec<u64>,
+    /// Set once a player has claimed a draw
+    claimed_draw: Option<DrawReason>,
+    /// How many plies deep the engine searches
+    difficulty: u32,
+    /// Characters print_board draws pieces with
+    charset: RenderStyle,
+    /// Whether print_board shows the board from the other side
+    flipped: bool,
+    clock: Option<TimeControl>,
+}
+
+/// Characters used to draw pieces
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RenderStyle {
+    /// Letters, upper case for White and lower case for Black
+    #[default]
+    Ascii,
+    /// Chess symbols such as ♘ and ♞
+    Unicode,
+}
+
+/// Time each player starts with and how much is added after every move they make
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeControl {
+    pub initial: Duration,
+    pub increment: Duration,
 }
 
 /// This enum represents the different colors the pieces can take
-#[derive(Copy, Clone)]
-enum Color {
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
     White,
     Black,
-    Empty,
 }
-/// Struct that represents the current position. x,y must be less than BOARD_DIMENSIONS
-#[derive(Copy, Clone)]
-struct Position {
-    x: i8,
-    y: i8,
+
+impl Color {
+    /// The other side: Black for White and White for Black
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+/// Struct that represents a square on the board. Unlike the usual (file, rank) order, x is the
+/// rank and y the file, both counted from 0: a1 is (0, 0), e2 is (1, 4) and h8 is (7, 7). White
+/// starts on ranks 0 and 1. Both must be less than BOARD_DIMENSIONS for the square to be on the
+/// board
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Rank, 0 for the first rank through 7 for the eighth
+    pub x: i8,
+    /// File, 0 for the a-file through 7 for the h-file
+    pub y: i8,
 }
 /// Struct that determines a movement in terms of a beginning and ending position
-#[derive(Copy, Clone)]
-struct Move {
-    start: Position,
-    end: Position,
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub start: Position,
+    pub end: Position,
+    /// What a pawn reaching the last rank turns into, a queen when left as `None`. Ignored for
+    /// every other move
+    pub promotion: Option<PieceType>,
 }
 
 /// Creates a structure that represents a chess Piece
 #[derive(Copy, Clone)]
-struct Piece {
-    board_rep: char,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Piece {
     piece_type: PieceType,
     captured: bool,
     first_move: bool,
     color: Color,
     position: Position,
-    is_move_valid: fn(Piece, Move) -> bool,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -53,40 +154,639 @@ pub enum PieceType {
     Rook,
     King,
     Queen,
-    None,
 }
 
-fn is_pawn_move_valid(pawn: Piece, movement: Move) -> bool {
+impl PieceType {
+    /// Material value in centipawns. The king is priceless and counts as nothing
+    pub fn value(self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 300,
+            PieceType::Bishop => 300,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 0,
+        }
+    }
+
+    /// Returns true for pieces that move any distance along a line: bishops, rooks and queens
+    pub fn is_sliding(self) -> bool {
+        matches!(self, PieceType::Bishop | PieceType::Rook | PieceType::Queen)
+    }
+
+    /// Letter for this type in FEN and on the board, upper case as for White
+    pub fn letter(self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Rook => 'R',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+}
+
+/// Everything needed to take back a move
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct MoveRecord {
+    mv: Move,
+    /// The moving piece as it stood before the move
+    moved: Piece,
+    /// Whatever the move captured, marked as captured, or `None` for a quiet move
+    captured: Option<Piece>,
+    /// Where the captured piece stood. Only differs from the move's end for en passant
+    captured_at: Position,
+    /// The en passant square before the move
+    en_passant: Option<Position>,
+    /// The castling rights before the move
+    castling: CastlingRights,
+    /// The halfmove clock before the move
+    halfmove_clock: u32,
+    /// The Zobrist hash before the move
+    hash: u64,
+}
+
+/// Reasons a move can be refused
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    /// The text could not be read as a move at all
+    Malformed(String),
+    /// The piece does not move that way
+    IllegalMove,
+    /// There is no piece on the start square
+    NoPiece,
+    /// The piece on the start square belongs to the side not to move
+    NotYourPiece,
+    /// The end square holds a piece of the mover's own side
+    OwnPieceOnTarget,
+    /// A piece stands in the way
+    PathBlocked,
+    /// The move would leave the mover's own king attacked
+    LeavesKingInCheck,
+    /// The side to move has no legal moves, so nothing can be played
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::Malformed(text) => write!(f, "'{}' is not a move", text),
+            MoveError::IllegalMove => write!(f, "illegal move"),
+            MoveError::NoPiece => write!(f, "no piece to move"),
+            MoveError::NotYourPiece => write!(f, "not your piece"),
+            MoveError::OwnPieceOnTarget => write!(f, "square occupied by your own piece"),
+            MoveError::PathBlocked => write!(f, "path blocked"),
+            MoveError::LeavesKingInCheck => write!(f, "king would be in check"),
+            MoveError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/// Reasons text describing part of a game could not be read
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseError {
+    /// Not the name of a square, like "e4"
+    InvalidSquare(String),
+    /// Not written like a move in algebraic notation
+    InvalidMove(String),
+    /// Written correctly, but no legal move matches it
+    NoMatchingMove(String),
+    /// More than one legal move matches, so the piece needs naming more precisely
+    AmbiguousMove(String),
+    /// The side to move has no piece of the kind named, on the file or rank given if any
+    NoSuchPiece(String),
+    /// Names a move only the side not to move could play
+    WrongTurn(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidSquare(square) => write!(f, "'{}' is not a square", square),
+            ParseError::InvalidMove(text) => write!(f, "'{}' is not a move", text),
+            ParseError::NoMatchingMove(text) => write!(f, "'{}' is not a legal move", text),
+            ParseError::AmbiguousMove(text) => {
+                write!(f, "'{}' could be more than one move", text)
+            }
+            ParseError::NoSuchPiece(text) => write!(f, "no piece can play '{}'", text),
+            ParseError::WrongTurn(text) => write!(f, "'{}' is a move for the other side", text),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Why a move file could not be replayed to the end. Line numbers count from 1
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The line is not a legal move
+    IllegalMove { line: usize, error: MoveError },
+    /// The line could not be read
+    Io { line: usize, error: io::Error },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::IllegalMove { line, error } => write!(f, "line {}: {}", line, error),
+            ReplayError::Io { line, error } => write!(f, "line {}: read failed: {}", line, error),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::IllegalMove { error, .. } => Some(error),
+            ReplayError::Io { error, .. } => Some(error),
+        }
+    }
+}
+
+/// The ways a game can finish
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
+}
+
+/// Why a game ended without a winner
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+/// Where the game stands from the point of view of the side to move
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameStatus {
+    InProgress,
+    /// The side to move is in check but has a way out
+    Check,
+    Checkmate,
+    Stalemate,
+    /// The game is drawn for a reason other than stalemate
+    Draw(DrawReason),
+}
+
+/// Draws the board in letters from White's side, the eighth rank at the top, with rank numbers
+/// down the left and file letters along the bottom
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(RenderStyle::Ascii))
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "Checkmate, White wins"),
+            GameResult::BlackWins => write!(f, "Checkmate, Black wins"),
+            GameResult::Draw(DrawReason::Stalemate) => write!(f, "Stalemate, the game is a draw"),
+            GameResult::Draw(DrawReason::InsufficientMaterial) => {
+                write!(f, "Draw by insufficient material")
+            }
+            GameResult::Draw(DrawReason::ThreefoldRepetition) => {
+                write!(f, "Draw by threefold repetition")
+            }
+            GameResult::Draw(DrawReason::FiftyMoveRule) => write!(f, "Draw by the fifty-move rule"),
+        }
+    }
+}
+
+impl Move {
+    /// The move in the coordinate notation UCI uses, such as "e2e4", with the piece a pawn
+    /// promotes to in lower case at the end, as in "e7e8q", when the move names one
+    pub fn to_uci(self) -> String {
+        let mut uci = self.start.to_algebraic() + &self.end.to_algebraic();
+        if let Some(promotion) = self.promotion {
+            uci.push(promotion.letter().to_ascii_lowercase());
+        }
+        uci
+    }
+}
+
+impl Position {
+    /// Name of the square in algebraic notation, such as "e4" for x = 3 and y = 4. The square
+    /// must be on the board
+    pub fn to_algebraic(self) -> String {
+        format!("{}{}", (b'a' + self.y as u8) as char, self.x + 1)
+    }
+
+    /// Reads a square name in algebraic notation, such as "e4", refusing anything that is not
+    /// a file from a to h followed by a rank from 1 to 8
+    pub fn from_algebraic(square: &str) -> Result<Position, ParseError> {
+        let invalid = || ParseError::InvalidSquare(square.to_string());
+        let bytes = square.as_bytes();
+        if bytes.len() != 2 || !square.is_ascii() {
+            return Err(invalid());
+        }
+        let pos = Position {
+            x: bytes[1] as i8 - b'1' as i8,
+            y: bytes[0] as i8 - b'a' as i8,
+        };
+        if pos.is_on_board() {
+            Ok(pos)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// Returns true if both coordinates are inside the board, so neither is negative or past
+    /// the last rank or file
+    pub fn is_on_board(&self) -> bool {
+        (0..BOARD_DIMENSIONS as i8).contains(&self.x)
+            && (0..BOARD_DIMENSIONS as i8).contains(&self.y)
+    }
+}
+
+impl Board {
+    /// The piece on the given square, or `None` if it is empty
+    fn square(&self, pos: Position) -> Option<Piece> {
+        self.state[pos.x as usize][pos.y as usize]
+    }
+
+    /// The piece on the given square, or `None` if it is empty or off the board
+    pub fn piece_at(&self, pos: Position) -> Option<&Piece> {
+        if !pos.is_on_board() {
+            return None;
+        }
+        self.state[pos.x as usize][pos.y as usize].as_ref()
+    }
+
+    /// Color of the piece on the given square, or `None` if it is empty
+    fn color_at(&self, pos: Position) -> Option<Color> {
+        self.square(pos).map(|piece| piece.color)
+    }
+
+    /// Type of the piece on the given square, or `None` if it is empty
+    fn piece_type_at(&self, pos: Position) -> Option<PieceType> {
+        self.square(pos).map(|piece| piece.piece_type)
+    }
+
+    /// Returns true if no piece stands on the given square
+    fn is_empty(&self, pos: Position) -> bool {
+        self.square(pos).is_none()
+    }
+
+    /// Puts `piece` on the square recorded in its position, replacing whatever was there
+    fn place(&mut self, piece: Piece) {
+        self.state[piece.position.x as usize][piece.position.y as usize] = Some(piece);
+    }
+
+    /// Empties the given square
+    fn clear(&mut self, pos: Position) {
+        self.state[pos.x as usize][pos.y as usize] = None;
+    }
+
+    /// Checks that every square strictly between the start and end of a straight or diagonal
+    /// move is empty. Moves along neither (knight jumps) are always clear
+    fn is_path_clear(&self, mv: Move) -> bool {
+        // Every step is checked against the edges so a path can never wrap around onto the
+        // other side of the board
+        squares_between(mv.start, mv.end)
+            .into_iter()
+            .all(|pos| pos.is_on_board() && self.is_empty(pos))
+    }
+
+    /// Returns true if any piece of color `by` could capture on `pos`. Pawns attack diagonally
+    /// forward, never the square they would push to, and a king only attacks the squares next
+    /// to it since castling never captures. A square holding one of `by`'s own pieces is never
+    /// attacked by it
+    pub fn is_square_attacked(&self, pos: Position, by: Color) -> bool {
+        if self.color_at(pos) == Some(by) {
+            return false;
+        }
+        self.pieces(by).any(|(start, &piece)| {
+            let rank_step = pos.x - start.x;
+            let file_step = pos.y - start.y;
+            match piece.piece_type {
+                PieceType::Pawn => {
+                    let forward = match by {
+                        Color::Black => -1,
+                        _ => 1,
+                    };
+                    rank_step == forward && file_step.abs() == 1
+                }
+                PieceType::King => rank_step.abs().max(file_step.abs()) == 1,
+                _ => {
+                    let mv = Move {
+                        start,
+                        end: pos,
+                        promotion: None,
+                    };
+                    self.is_pseudo_legal(piece, mv) && self.is_path_clear(mv)
+                }
+            }
+        })
+    }
+
+    /// Checks `mv` against the rule for how `piece` moves. The board is there for rules that
+    /// depend on what stands on the path or the destination, like pawn captures. No piece may
+    /// land on one of its own side's pieces
+    fn is_pseudo_legal(&self, piece: Piece, mv: Move) -> bool {
+        if self.piece_at(mv.end).map(|target| target.color) == Some(piece.color) {
+            return false;
+        }
+        match piece.kind() {
+            PieceType::Pawn => is_pawn_move_valid(piece, mv, self),
+            PieceType::Knight => is_knight_move_valid(piece, mv, self),
+            PieceType::Bishop => is_bishop_move_valid(piece, mv, self),
+            PieceType::Rook => is_rook_move_valid(piece, mv, self),
+            PieceType::Queen => is_queen_move_valid(piece, mv, self),
+            PieceType::King => is_king_move_valid(piece, mv, self),
+        }
+    }
+
+    /// Moves the piece on the start square of `mv` to its end square, leaving the start square
+    /// empty, and returns whatever stood on the end square marked as captured. Only those two
+    /// squares change, so removing an en passant victim or moving a castling rook is up to the
+    /// caller. A pawn reaching the last rank is replaced by the piece `mv` promotes it to. Rules
+    /// are not checked, and nothing happens if the start square is empty
+    pub fn apply_move(&mut self, mv: Move) -> Option<Piece> {
+        let mut piece = self.square(mv.start)?;
+        let captured = self.square(mv.end).map(|mut captured| {
+            captured.captured = true;
+            captured
+        });
+        if piece.piece_type == PieceType::Pawn && mv.end.x == promotion_rank(piece.color) {
+            let promoted = mv.promotion.unwrap_or(PieceType::Queen);
+            piece = Piece::new(promoted, piece.color, mv.start);
+        }
+        piece.first_move = false;
+        // Keep the piece's own record of its square in step with where it now stands
+        piece.position = mv.end;
+        self.place(piece);
+        self.clear(mv.start);
+        captured
+    }
+
+    /// Draws the board in the given style from White's side, one rank per line with '_' for
+    /// empty squares, labelled with rank numbers and file letters
+    pub fn render(&self, style: RenderStyle) -> String {
+        self.draw(style, Color::White)
+    }
+
+    /// Draws the board in letters from `pov`'s side, so for Black it is turned around with the
+    /// first rank at the top and the files running from h to a
+    pub fn render_from(&self, pov: Color) -> String {
+        self.draw(RenderStyle::Ascii, pov)
+    }
+
+    /// Draws the board in the given style with `pov`'s pieces at the bottom
+    fn draw(&self, style: RenderStyle, pov: Color) -> String {
+        let last = BOARD_DIMENSIONS - 1;
+        // Seen from Black's side both ranks and files run the other way
+        let orient = |index: usize| match pov {
+            Color::White => last - index,
+            Color::Black => index,
+        };
+        let mut out = String::new();
+        for row in 0..BOARD_DIMENSIONS {
+            let rank = orient(row);
+            out.push_str(&format!("{} ", rank + 1));
+            for column in 0..BOARD_DIMENSIONS {
+                let file = last - orient(column);
+                out.push(self.state[rank][file].map_or('_', |piece| piece.symbol(style)));
+            }
+            out.push('\n');
+        }
+        out.push_str("  ");
+        for column in 0..BOARD_DIMENSIONS {
+            out.push((b'a' + (last - orient(column)) as u8) as char);
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Returns true if every piece's own record of its square matches where it stands
+    fn positions_in_sync(&self) -> bool {
+        self.iter_pieces().all(|(pos, piece)| piece.position == pos)
+    }
+
+    /// Every square that currently holds a piece
+    fn occupied_squares(&self) -> impl Iterator<Item = Position> + '_ {
+        all_squares().filter(move |&pos| !self.is_empty(pos))
+    }
+
+    /// Every piece on the board along with the square it stands on, rank by rank from a1
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        all_squares().filter_map(move |pos| self.square(pos).map(|piece| (pos, piece)))
+    }
+
+    /// Every piece of the given color along with the square it stands on, rank by rank from a1.
+    /// Captured pieces are off the board, so they never show up
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = (Position, &Piece)> + '_ {
+        all_squares().filter_map(move |pos| {
+            self.piece_at(pos)
+                .filter(|piece| piece.color == color)
+                .map(|piece| (pos, piece))
+        })
+    }
+
+    /// Squares of every piece of the given type and color, rank by rank from a1
+    pub fn pieces_of(&self, piece_type: PieceType, color: Color) -> Vec<Position> {
+        self.pieces(color)
+            .filter(|(_, piece)| piece.piece_type == piece_type)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// The board as a flat array in rank-major order: index `rank * 8 + file`, so index 0 is a1,
+    /// index 7 is h1 and index 63 is h8. Empty squares are `None`
+    pub fn squares(&self) -> [Option<Piece>; BOARD_DIMENSIONS * BOARD_DIMENSIONS] {
+        let mut squares = [None; BOARD_DIMENSIONS * BOARD_DIMENSIONS];
+        for (pos, piece) in self.iter_pieces() {
+            squares[pos.x as usize * BOARD_DIMENSIONS + pos.y as usize] = Some(piece);
+        }
+        squares
+    }
+
+    /// Returns a copy of the board flipped top to bottom with every piece changing sides, so
+    /// White's pieces on rank 1 become Black's pieces on rank 8 and vice versa
+    pub fn mirrored(&self) -> Board {
+        let mut mirror = Board {
+            state: [[None; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
+        };
+        for (pos, mut piece) in self.iter_pieces() {
+            // The letter and move rules follow from the type and color, so only the color changes
+            piece.color = piece.color.opposite();
+            piece.position = Position {
+                x: BOARD_DIMENSIONS as i8 - 1 - pos.x,
+                y: pos.y,
+            };
+            mirror.place(piece);
+        }
+        mirror
+    }
+
+    /// A file is open when there are no pawns of either color on it
+    pub fn is_open_file(&self, file: usize) -> bool {
+        self.is_half_open_file(file, Color::White) && self.is_half_open_file(file, Color::Black)
+    }
+
+    /// A file is half-open for a color when that color has no pawns on it
+    pub fn is_half_open_file(&self, file: usize, color: Color) -> bool {
+        !self.state.iter().any(|rank| {
+            rank[file]
+                .is_some_and(|piece| piece.piece_type == PieceType::Pawn && piece.color == color)
+        })
+    }
+
+    /// Where the king of the given color stands, if it is on the board
+    fn find_king(&self, color: Color) -> Option<Position> {
+        self.iter_pieces()
+            .find(|(_, piece)| piece.piece_type == PieceType::King && piece.color == color)
+            .map(|(pos, _)| pos)
+    }
+}
+
+/// Two boards are equal when every square holds the same type and color of piece
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        all_squares().all(|pos| {
+            self.piece_type_at(pos) == other.piece_type_at(pos)
+                && self.color_at(pos) == other.color_at(pos)
+        })
+    }
+}
+
+/// The squares strictly between two positions on the same rank, file or diagonal, ordered from
+/// `a` towards `b`. Positions that are not lined up have nothing between them
+pub fn squares_between(a: Position, b: Position) -> Vec<Position> {
+    let x = b.x - a.x;
+    let y = b.y - a.y;
+    if x != 0 && y != 0 && x.abs() != y.abs() {
+        return Vec::new();
+    }
+    let steps = x.abs().max(y.abs());
+    (1..steps)
+        .map(|step| Position {
+            x: a.x + step * x.signum(),
+            y: a.y + step * y.signum(),
+        })
+        .collect()
+}
+
+/// Whether `piece` can slide from the start of `movement` to its end: every square in between
+/// must be empty, and the last one may only hold an enemy piece to capture
+fn is_slide_open(piece: Piece, movement: Move, board: &Board) -> bool {
+    if !movement.end.is_on_board() || board.color_at(movement.end) == Some(piece.color) {
+        return false;
+    }
+    squares_between(movement.start, movement.end)
+        .into_iter()
+        .all(|pos| board.is_empty(pos))
+}
+
+/// Whether `piece` can slide along a rank or file to the end of `movement`
+fn is_path_clear_straight(piece: Piece, movement: Move, board: &Board) -> bool {
+    let x = movement.end.x - movement.start.x;
+    let y = movement.end.y - movement.start.y;
+    (x == 0) != (y == 0) && is_slide_open(piece, movement, board)
+}
+
+/// Whether `piece` can slide along a diagonal to the end of `movement`
+fn is_path_clear_diagonal(piece: Piece, movement: Move, board: &Board) -> bool {
+    let x = movement.end.x - movement.start.x;
+    let y = movement.end.y - movement.start.y;
+    x != 0 && x.abs() == y.abs() && is_slide_open(piece, movement, board)
+}
+
+/// How much `piece` adds to the material balance, counting White as positive
+fn material_swing(piece: Piece) -> i32 {
+    match piece.color {
+        Color::Black => -piece.piece_type.value(),
+        _ => piece.piece_type.value(),
+    }
+}
+
+/// Reads a move written as its start and end squares, such as "e2e4"
+fn parse_coordinate_move(text: &str) -> Result<Move, MoveError> {
+    let malformed = || MoveError::Malformed(text.to_string());
+    if text.len() != 4 || !text.is_ascii() {
+        return Err(malformed());
+    }
+    Ok(Move {
+        start: Position::from_algebraic(&text[..2]).map_err(|_| malformed())?,
+        end: Position::from_algebraic(&text[2..]).map_err(|_| malformed())?,
+        promotion: None,
+    })
+}
+
+/// Where `color` keeps its time in a pair of clock readings, White first
+fn clock_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+/// Time on a clock as minutes and seconds, such as "4:05"
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// The rank a pawn of `color` promotes on
+fn promotion_rank(color: Color) -> i8 {
+    match color {
+        Color::White => BOARD_DIMENSIONS as i8 - 1,
+        Color::Black => 0,
+    }
+}
+
+/// Every square on the board, rank by rank
+fn all_squares() -> impl Iterator<Item = Position> {
+    (0..BOARD_DIMENSIONS as i8)
+        .flat_map(|x| (0..BOARD_DIMENSIONS as i8).map(move |y| Position { x, y }))
+}
+
+fn is_pawn_move_valid(pawn: Piece, movement: Move, board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
+        return false;
+    }
     if pawn.captured {
         return false;
     }
     //Can only move forward one unless it is the first time this Piece is moving
-    let mut y = movement.end.y - movement.start.y;
-    //Since the "white" player is at the bottom, we flip the value for this check if the piece
+    //Ranks run along x, so forward is a change in x
+    let mut x = movement.end.x - movement.start.x;
+    //Since the "white" player starts on rank 0, we flip the value for this check if the piece
     //is black
-    match pawn.color {
-        Color::Black => y = -y,
-        Color::White => {}
-        Color::Empty => {}
-    }
-    let x = movement.end.x - movement.start.x; //Currently not checking for attack
-                                               //This checks if the pawn is moving one block forward or two blocks if it's the first move
-    if ((y == 1) || (y == 2 && pawn.first_move)) && x == 0 {
+    if pawn.color == Color::Black {
+        x = -x;
+    }
+    let y = movement.end.y - movement.start.y;
+    //This checks if the pawn is moving one block forward or two blocks if it's the first move
+    if ((x == 1) || (x == 2 && pawn.first_move)) && y == 0 {
         return true;
     }
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
-        return false;
+    //Captures are one block diagonally forward onto an enemy piece. En passant lands on an
+    //empty square, so Game::is_pawn_pseudo_legal allows it separately
+    if x == 1 && y.abs() == 1 {
+        return board
+            .color_at(movement.end)
+            .is_some_and(|color| color != pawn.color);
     }
-    return false;
+    false
 }
 
-fn is_rook_move_valid(rook: Piece, movement: Move) -> bool {
-    if rook.captured {
+fn is_rook_move_valid(rook: Piece, movement: Move, board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
         return false;
     }
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
+    if rook.captured {
         return false;
     }
     //Can move in a straight line the entire length of the board
@@ -96,16 +796,16 @@ fn is_rook_move_valid(rook: Piece, movement: Move) -> bool {
         // This is a diagonal move, so invalid
         return false;
     }
-    // TODO Check if the Rook is jumping over a piece here
-    return true;
+    // A rook cannot jump, so every square on the way must be empty
+    is_path_clear_straight(rook, movement, board)
 }
 
-fn is_bishop_move_valid(bishop: Piece, movement: Move) -> bool {
-    if bishop.captured {
+fn is_bishop_move_valid(bishop: Piece, movement: Move, board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
         return false;
     }
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
+    if bishop.captured {
         return false;
     }
     //Can move diagonally across the entire board
@@ -116,16 +816,16 @@ fn is_bishop_move_valid(bishop: Piece, movement: Move) -> bool {
     if x.abs() != y.abs() {
         return false;
     }
-    // TODO Check if this is jumping over a Piece here
-    return true;
+    // Step along the diagonal one square at a time, since a bishop cannot jump either
+    is_path_clear_diagonal(bishop, movement, board)
 }
 
-fn is_knight_move_valid(knight: Piece, movement: Move) -> bool {
-    if knight.captured {
+fn is_knight_move_valid(knight: Piece, movement: Move, _board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
         return false;
     }
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
+    if knight.captured {
         return false;
     }
     let x = movement.start.x - movement.end.x;
@@ -137,92 +837,147 @@ fn is_knight_move_valid(knight: Piece, movement: Move) -> bool {
     //x= +/-1, +/-2
     //y= +/-1, +/-2
     //Which are all valid moves
-    if one_norm == 3 && two_norm_square == 5 {
-        return true;
-    } else {
-        return false;
-    }
+    one_norm == 3 && two_norm_square == 5
 }
 
-fn is_queen_move_valid(queen: Piece, movement: Move) -> bool {
-    if queen.captured {
+fn is_queen_move_valid(queen: Piece, movement: Move, board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
         return false;
     }
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
+    if queen.captured {
         return false;
     }
     let x = movement.end.x - movement.start.x;
     let y = movement.end.y - movement.start.y;
-    // A Queen can move as a rook or as a bishop
-    // We section them off this way so that we can more easily implement checking for piece
-    // jumping
-    if x != 0 && y == 0 {
-        // TODO check for piece jumping along x axis
-        return true;
-    } else if x == 0 && y != 0 {
-        // TODO check for piece jumping along y axis
-        return true;
+    // A Queen can move as a rook or as a bishop, and is blocked the same way they are
+    if (x != 0 && y == 0) || (x == 0 && y != 0) {
+        return is_path_clear_straight(queen, movement, board);
     }
     // Now we check as if the Queen is a bishop
     // Check along y = x with origin at movement.start
-    if x == y {
-        // Check for jumping a piece
-        return true;
-    } else if x == -y {
-        // Check for jumping a piece
-        return true;
+    if x == y || x == -y {
+        return is_path_clear_diagonal(queen, movement, board);
     }
     //Now we are not moving like a bishop nor a rook so we fail
-    return false;
+    false
 }
 
-fn is_king_move_valid(king: Piece, movement: Move) -> bool {
-    if king.captured {
+fn is_king_move_valid(king: Piece, movement: Move, board: &Board) -> bool {
+    //This checks if the piece is trying to move off the board
+    if !movement.end.is_on_board() {
         return false;
     }
-
-    if movement.end.x >= BOARD_DIMENSIONS as i8 || movement.end.y >= BOARD_DIMENSIONS as i8 {
-        //This checks if the piece is trying to move off the board
+    if king.captured {
         return false;
     }
 
+    // Castling depends on the castling rights, which the board does not know, so
+    // Game::is_pseudo_legal recognizes it before asking here
     let x = movement.end.x - movement.start.x;
     let y = movement.end.y - movement.start.y;
-    // TODO: Check for a castling move
-    if x > 1 || y > 1 {
+    // The king steps one square in any direction, so either component may be negative
+    if x.abs() > 1 || y.abs() > 1 || (x == 0 && y == 0) {
         return false;
     }
-    // TODO: Implement checking for "check" maybe as a separate function?
-    return true;
+    // Stepping into check is left to Game::is_in_check, which needs the whole game rather than
+    // just the board. The step itself may only land on an empty square or capture an enemy
+    board.color_at(movement.end) != Some(king.color)
+}
+
+impl Piece {
+    /// Character drawn for this piece in the given style
+    fn symbol(&self, style: RenderStyle) -> char {
+        if style == RenderStyle::Ascii {
+            return self.board_rep();
+        }
+        match (self.color, self.piece_type) {
+            (Color::White, PieceType::King) => '♔',
+            (Color::White, PieceType::Queen) => '♕',
+            (Color::White, PieceType::Rook) => '♖',
+            (Color::White, PieceType::Bishop) => '♗',
+            (Color::White, PieceType::Knight) => '♘',
+            (Color::White, PieceType::Pawn) => '♙',
+            (Color::Black, PieceType::King) => '♚',
+            (Color::Black, PieceType::Queen) => '♛',
+            (Color::Black, PieceType::Rook) => '♜',
+            (Color::Black, PieceType::Bishop) => '♝',
+            (Color::Black, PieceType::Knight) => '♞',
+            (Color::Black, PieceType::Pawn) => '♟',
+        }
+    }
+
+    pub fn piece_type(&self) -> PieceType {
+        self.piece_type
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Same as piece_type, naming what kind of piece this is whatever its color
+    pub fn kind(&self) -> PieceType {
+        self.piece_type
+    }
+
+    /// Letter for this piece in FEN and on the board, upper case for White and lower case for
+    /// Black
+    pub fn board_rep(&self) -> char {
+        match self.color {
+            Color::White => self.piece_type.letter(),
+            Color::Black => self.piece_type.letter().to_ascii_lowercase(),
+        }
+    }
+
+    /// Creates a piece standing on `position` that has not moved yet
+    fn new(piece_type: PieceType, color: Color, position: Position) -> Piece {
+        Piece {
+            piece_type,
+            captured: false,
+            first_move: true,
+            color,
+            position,
+        }
+    }
 }
 
-fn empty_piece_move(_empty: Piece, _empty_move: Move) -> bool {
-    return false;
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
 }
 
 impl Game {
-    /// Create a new game
-    pub fn new() -> Game {
-        let mut new_game = Game {
+    /// A game with nothing on the board and White to move, for the other constructors to fill
+    fn empty() -> Game {
+        Game {
             turn: Color::White,
+            en_passant: None,
+            castling: CastlingRights::default(),
+            history: Vec::new(),
+            material: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            position_hashes: Vec::new(),
+            claimed_draw: None,
+            difficulty: DEFAULT_DIFFICULTY,
+            charset: RenderStyle::Ascii,
+            flipped: false,
+            clock: None,
             board: Board {
-                // Initializes the state of the board as "empty" pieces to be updated during the
-                // next step
-                state: [[Piece {
-                    board_rep: '_',
-                    piece_type: PieceType::None,
-                    captured: true,
-                    first_move: false,
-                    color: Color::Empty,
-                    position: Position { x: -1, y: -1 },
-                    is_move_valid: empty_piece_move,
-                }; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
+                // Initializes every square as empty to be filled by the constructor
+                state: [[None; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
             },
-        };
+        }
+    }
+
+    /// Create a new game
+    pub fn new() -> Game {
+        let mut new_game = Game::empty();
         // Initialize board state
         // White pawns
-        // NOTE: White on top, Black on bottom
+        // NOTE: White starts on ranks 1 and 2, drawn at the bottom, and Black on ranks 7 and 8
         for file in 0..BOARD_DIMENSIONS {
             // creates a position the pawn is going to be (not needed for drawing but needed for
             // move creation later. Be careful to not desync these. A smarter implementation is
@@ -232,90 +987,72 @@ impl Game {
                 y: file as i8,
             };
             let temp_pawn = Piece {
-                board_rep: 'P',
                 piece_type: PieceType::Pawn,
                 captured: false,
                 first_move: true,
                 color: Color::White,
                 position: temp_pos,
-                is_move_valid: is_pawn_move_valid,
             };
-            new_game.board.state[1][file] = temp_pawn;
+            new_game.board.place(temp_pawn);
         }
         // Here, we are not going to create the Piece and position on separate lines and will
         // follow the creation flow on new_game.state initialization
-        new_game.board.state[0][0] = Piece {
-            board_rep: 'R',
+        new_game.board.place(Piece {
             piece_type: PieceType::Rook,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 0 },
-            is_move_valid: is_rook_move_valid,
-        };
-        new_game.board.state[0][1] = Piece {
-            board_rep: 'N',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Knight,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 1 },
-            is_move_valid: is_knight_move_valid,
-        };
-        new_game.board.state[0][2] = Piece {
-            board_rep: 'B',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Bishop,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 2 },
-            is_move_valid: is_rook_move_valid,
-        };
-        new_game.board.state[0][3] = Piece {
-            board_rep: 'Q',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Queen,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 3 },
-            is_move_valid: is_queen_move_valid,
-        };
-        new_game.board.state[0][4] = Piece {
-            board_rep: 'K',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::King,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 4 },
-            is_move_valid: is_king_move_valid,
-        };
-        new_game.board.state[0][5] = Piece {
-            board_rep: 'B',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Bishop,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 5 },
-            is_move_valid: is_bishop_move_valid,
-        };
-        new_game.board.state[0][6] = Piece {
-            board_rep: 'N',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Knight,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 6 },
-            is_move_valid: is_knight_move_valid,
-        };
-        new_game.board.state[0][7] = Piece {
-            board_rep: 'R',
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Rook,
             captured: false,
             first_move: true,
             color: Color::White,
             position: Position { x: 0, y: 7 },
-            is_move_valid: is_rook_move_valid,
-        };
+        });
 
         for file in 0..BOARD_DIMENSIONS {
             // creates a position the pawn is going to be (not needed for drawing but needed for
@@ -326,108 +1063,1053 @@ impl Game {
                 y: file as i8,
             };
             let temp_pawn = Piece {
-                board_rep: 'p',
                 piece_type: PieceType::Pawn,
                 captured: false,
                 first_move: true,
                 color: Color::Black,
                 position: temp_pos,
-                is_move_valid: is_pawn_move_valid,
             };
-            new_game.board.state[6][file] = temp_pawn;
+            new_game.board.place(temp_pawn);
         }
         // Here, we are not going to create the Piece and position on separate lines and will
         // follow the creation flow on new_game.state initialization
-        new_game.board.state[7][0] = Piece {
-            board_rep: 'r',
+        new_game.board.place(Piece {
             piece_type: PieceType::Rook,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 0 },
-            is_move_valid: is_rook_move_valid,
-        };
-        new_game.board.state[7][1] = Piece {
-            board_rep: 'n',
+            position: Position { x: 7, y: 0 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Knight,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 1 },
-            is_move_valid: is_knight_move_valid,
-        };
-        new_game.board.state[7][2] = Piece {
-            board_rep: 'b',
+            position: Position { x: 7, y: 1 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Bishop,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 2 },
-            is_move_valid: is_rook_move_valid,
-        };
-        new_game.board.state[7][3] = Piece {
-            board_rep: 'q',
+            position: Position { x: 7, y: 2 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Queen,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 3 },
-            is_move_valid: is_queen_move_valid,
-        };
-        new_game.board.state[7][4] = Piece {
-            board_rep: 'k',
+            position: Position { x: 7, y: 3 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::King,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 4 },
-            is_move_valid: is_king_move_valid,
-        };
-        new_game.board.state[7][5] = Piece {
-            board_rep: 'b',
+            position: Position { x: 7, y: 4 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Bishop,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 5 },
-            is_move_valid: is_bishop_move_valid,
-        };
-        new_game.board.state[7][6] = Piece {
-            board_rep: 'n',
+            position: Position { x: 7, y: 5 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Knight,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 6 },
-            is_move_valid: is_knight_move_valid,
-        };
-        new_game.board.state[7][7] = Piece {
-            board_rep: 'r',
+            position: Position { x: 7, y: 6 },
+        });
+        new_game.board.place(Piece {
             piece_type: PieceType::Rook,
             captured: false,
             first_move: true,
             color: Color::Black,
-            position: Position { x: 0, y: 7 },
-            is_move_valid: is_rook_move_valid,
-        };
+            position: Position { x: 7, y: 7 },
+        });
 
-        return new_game;
+        new_game.castling = CastlingRights::all();
+        new_game.start_hashing();
+        new_game
     }
 
-    /// Main game loop
-    pub fn run_game(&mut self) {
-        let game_over: bool = false;
-        while !game_over {
-            self.print_board();
-            println!();
-
+    /// A game whose first rank holds `white_order` from the a-file to the h-file, with pawns in
+    /// front and Black mirroring White, for Chess960 and other custom starts. The order must
+    /// hold exactly one king and no pawns. Each side may castle toward any side of the king
+    /// that holds a rook, with the outermost one if there are two, and the king and rook land
+    /// on the same squares as in standard chess
+    pub fn from_back_rank(white_order: [PieceType; 8]) -> Result<Game, PositionError> {
+        let mut game = Game::empty();
+        let last_rank = BOARD_DIMENSIONS as i8 - 1;
+        for &color in &[Color::White, Color::Black] {
+            let (back_rank, pawn_rank) = match color {
+                Color::White => (0, 1),
+                Color::Black => (last_rank, last_rank - 1),
+            };
+            for (file, &piece_type) in white_order.iter().enumerate() {
+                for &(rank, piece_type) in &[(back_rank, piece_type), (pawn_rank, PieceType::Pawn)]
+                {
+                    game.board.place(Piece {
+                        piece_type,
+                        captured: false,
+                        first_move: true,
+                        color,
+                        position: Position {
+                            x: rank,
+                            y: file as i8,
+                        },
+                    });
+                }
+            }
+        }
+        game.validate_position()?;
+
+        let king_file = white_order
+            .iter()
+            .position(|&piece_type| piece_type == PieceType::King)
+            .expect("a validated position has a king");
+        let kingside = white_order[king_file + 1..].contains(&PieceType::Rook);
+        let queenside = white_order[..king_file].contains(&PieceType::Rook);
+        game.castling = CastlingRights {
+            white_king: kingside,
+            white_queen: queenside,
+            black_king: kingside,
+            black_queen: queenside,
+            ..CastlingRights::default()
+        };
+        game.castling.locate_rooks(&game.board);
+        game.material = game.count_material();
+        game.start_hashing();
+        Ok(game)
+    }
+
+    /// Returns the type and color of the piece `mv` would capture, or `None` for a quiet move.
+    /// An en passant capture reports the pawn being passed rather than the empty landing square
+    pub fn capture_target(&self, mv: Move) -> Option<(PieceType, Color)> {
+        let mover = self.board.square(mv.start)?;
+        if let Some(target) = self.board.square(mv.end) {
+            if target.color == mover.color {
+                return None;
+            }
+            return Some((target.piece_type, target.color));
+        }
+        // An en passant capture lands on an empty square, so the victim sits beside the mover
+        match self.en_passant {
+            Some(ep)
+                if mover.piece_type == PieceType::Pawn
+                    && ep == mv.end
+                    && mv.start.y != mv.end.y =>
+            {
+                let victim = self.board.square(Position {
+                    x: mv.start.x,
+                    y: mv.end.y,
+                })?;
+                Some((victim.piece_type, victim.color))
+            }
+            _ => None,
+        }
+    }
+
+    /// The type and color of the piece on a square given in algebraic notation, such as "e1",
+    /// or `None` if the square is empty
+    pub fn piece_at_square(&self, square: &str) -> Result<Option<(PieceType, Color)>, ParseError> {
+        let piece = self.board.square(Position::from_algebraic(square)?);
+        Ok(piece.map(|piece| (piece.piece_type, piece.color)))
+    }
+
+    /// Returns true if `mv` would take an enemy piece, including en passant
+    pub fn is_capture(&self, mv: Move) -> bool {
+        self.capture_target(mv).is_some()
+    }
+
+    /// Returns true if `mv` is a castle the side on its start square still has the right to
+    /// make. It is written as the king moving two squares along its rank when the king starts
+    /// on the e-file, and otherwise as the king moving onto the rook it castles with
+    pub fn is_castle(&self, mv: Move) -> bool {
+        self.castle_side(mv).is_some()
+    }
+
+    /// Checks `mv` against the moving piece's rules plus everything that needs the rest of the
+    /// board: blocked paths, landing on a friendly piece and pawn captures. Ignores whose turn
+    /// it is and whether the mover's own king is left in check
+    fn is_pseudo_legal(&self, mv: Move) -> bool {
+        if !mv.start.is_on_board() || !mv.end.is_on_board() {
+            return false;
+        }
+        let piece = match self.board.square(mv.start) {
+            Some(piece) => piece,
+            None => return false,
+        };
+        // Only a pawn reaching the last rank promotes, and never to a pawn or a king
+        if let Some(promotion) = mv.promotion {
+            if piece.piece_type != PieceType::Pawn
+                || mv.end.x != promotion_rank(piece.color)
+                || matches!(promotion, PieceType::Pawn | PieceType::King)
+            {
+                return false;
+            }
+        }
+        if piece.piece_type == PieceType::Pawn {
+            return self.is_pawn_pseudo_legal(piece, mv);
+        }
+        // A castle may land the king on its own rook, which no piece's rule allows
+        if self.is_castle(mv) {
+            return self.can_castle(mv);
+        }
+        self.board.is_pseudo_legal(piece, mv) && self.board.is_path_clear(mv)
+    }
+
+    /// Pawns push onto empty squares only and capture diagonally, including en passant
+    fn is_pawn_pseudo_legal(&self, pawn: Piece, mv: Move) -> bool {
+        if mv.start.y == mv.end.y {
+            return self.board.is_pseudo_legal(pawn, mv)
+                && self.board.is_empty(mv.end)
+                && self.board.is_path_clear(mv);
+        }
+        if self.board.is_pseudo_legal(pawn, mv) {
+            return true;
+        }
+        // The board does not know about en passant, so the validator cannot allow it
+        let forward = match pawn.color {
+            Color::Black => -1,
+            _ => 1,
+        };
+        self.en_passant == Some(mv.end)
+            && mv.end.x - mv.start.x == forward
+            && (mv.end.y - mv.start.y).abs() == 1
+    }
+
+    /// Works out why the side to move cannot play `mv`, checking in the same order as
+    /// is_pseudo_legal so the first rule broken is the one reported
+    fn rejection_reason(&self, mv: Move) -> MoveError {
+        if !mv.start.is_on_board() || !mv.end.is_on_board() {
+            return MoveError::IllegalMove;
+        }
+        let piece = match self.board.square(mv.start) {
+            Some(piece) => piece,
+            None => return MoveError::NoPiece,
+        };
+        if piece.color != self.turn {
+            return MoveError::NotYourPiece;
+        }
+        if self.is_castle(mv) {
+            return if !self.is_castle_path_clear(mv) {
+                MoveError::PathBlocked
+            } else if self.can_castle(mv) && self.leaves_king_in_check(mv) {
+                MoveError::LeavesKingInCheck
+            } else {
+                MoveError::IllegalMove
+            };
+        }
+        if self.board.color_at(mv.end) == Some(piece.color) {
+            return MoveError::OwnPieceOnTarget;
+        }
+        let is_push = piece.piece_type == PieceType::Pawn && mv.start.y == mv.end.y;
+        if piece.piece_type != PieceType::Pawn || is_push {
+            if !self.board.is_pseudo_legal(piece, mv) {
+                // Validators that look at the path refuse blocked moves too, so ask again with
+                // the path emptied to tell the two apart
+                let mut open = self.board.clone();
+                for pos in squares_between(mv.start, mv.end) {
+                    open.clear(pos);
+                }
+                if open.is_pseudo_legal(piece, mv) {
+                    return MoveError::PathBlocked;
+                }
+                return MoveError::IllegalMove;
+            }
+            // A pawn cannot push onto a piece any more than through one
+            if !self.board.is_path_clear(mv) || (is_push && !self.board.is_empty(mv.end)) {
+                return MoveError::PathBlocked;
+            }
+        } else if !self.is_pawn_pseudo_legal(piece, mv) {
+            return MoveError::IllegalMove;
+        }
+        if self.leaves_king_in_check(mv) {
+            return MoveError::LeavesKingInCheck;
+        }
+        MoveError::IllegalMove
+    }
+
+    /// Plays `mv` for the side to move if it is legal, remembering it so that it can be taken
+    /// back with unmake_move. An illegal move is refused with the reason it is illegal, or with
+    /// `GameOver` when the side to move has no legal moves at all
+    pub fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if !self.is_pseudo_legal(mv)
+            || self.board.color_at(mv.start) != Some(self.turn)
+            || self.leaves_king_in_check(mv)
+        {
+            // Only worth looking for other moves once this one has been refused
+            if !self.has_legal_moves() {
+                return Err(MoveError::GameOver);
+            }
+            return Err(self.rejection_reason(mv));
+        }
+        let record = self.apply_move(mv);
+        debug_assert_eq!(
+            self.hash,
+            self.full_zobrist_hash(),
+            "hash drifted after a move"
+        );
+        self.history.push(record);
+        self.position_hashes.push(self.hash);
+        Ok(())
+    }
+
+    /// Plays a move written in UCI notation, such as "e2e4", for the side to move. A promotion
+    /// suffix like the "q" in "e7e8q" is accepted only on a pawn reaching the last rank
+    pub fn make_uci_move(&mut self, uci: &str) -> Result<(), MoveError> {
+        let malformed = || MoveError::Malformed(uci.to_string());
+        let (squares, promotion) = if uci.len() == 5 && uci.is_ascii() {
+            (&uci[..4], uci.chars().last())
+        } else {
+            (uci, None)
+        };
+        let mut mv = parse_coordinate_move(squares).map_err(|_| malformed())?;
+        if let Some(letter) = promotion {
+            if !"qrbn".contains(letter) {
+                return Err(malformed());
+            }
+            if self.board.piece_type_at(mv.start) != Some(PieceType::Pawn)
+                || mv.end.x != promotion_rank(self.turn)
+            {
+                return Err(MoveError::IllegalMove);
+            }
+            mv.promotion = piece_from_san_letter(letter.to_ascii_uppercase());
+        }
+        self.make_move(mv)
+    }
+
+    /// Takes back the last move played through make_move, restoring any captured piece, and
+    /// returns it. Does nothing and returns `None` if no moves have been played
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let record = self.history.pop()?;
+        self.revert_move(record);
+        self.position_hashes.pop();
+        debug_assert_eq!(
+            self.hash,
+            self.full_zobrist_hash(),
+            "hash drifted after a takeback"
+        );
+        self.claimed_draw = None;
+        Some(record.mv)
+    }
+
+    /// Takes back the last move, putting the board, side to move, castling rights, en passant
+    /// square and both move counters back exactly as they were before it. The same as
+    /// unmake_move, under the name the `undo` command goes by
+    pub fn undo(&mut self) -> Option<Move> {
+        self.unmake_move()
+    }
+
+    /// Undoes a move made by apply_move, putting back everything it changed
+    fn revert_move(&mut self, record: MoveRecord) {
+        let mv = record.mv;
+        if let Some(rook_move) = castling::rook_move(&record.castling, record.moved, mv) {
+            // Both pieces come off before either goes back, as either may return to a square
+            // the other is standing on
+            let rook = self.board.square(rook_move.end);
+            self.board.clear(castling::king_landing(rook_move));
+            self.board.clear(rook_move.end);
+            if let Some(mut rook) = rook {
+                rook.first_move = true;
+                rook.position = rook_move.start;
+                self.board.place(rook);
+            }
+            self.board.place(record.moved);
+        } else {
+            // A promoted piece gives its value back as it turns into a pawn again
+            if let Some(landed) = self.board.square(mv.end) {
+                self.material += material_swing(record.moved) - material_swing(landed);
+            }
+            self.board.clear(mv.end);
+            if let Some(mut restored) = record.captured {
+                restored.captured = false;
+                self.board.state[record.captured_at.x as usize][record.captured_at.y as usize] =
+                    Some(restored);
+            }
+            self.board.place(record.moved);
+        }
+        self.en_passant = record.en_passant;
+        self.castling = record.castling;
+        self.turn = record.moved.color;
+        if let Some(captured) = record.captured {
+            self.material += material_swing(captured);
+        }
+        self.halfmove_clock = record.halfmove_clock;
+        if record.moved.color == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.hash = record.hash;
+    }
+
+    /// Moves a piece without checking legality, removes anything it captures and passes the
+    /// turn to the other player. Returns what is needed to take the move back
+    fn apply_move(&mut self, mv: Move) -> MoveRecord {
+        let piece = self
+            .board
+            .square(mv.start)
+            .expect("apply_move needs a piece on the start square");
+        let is_pawn = piece.piece_type == PieceType::Pawn;
+        let rook_move = castling::rook_move(&self.castling, piece, mv);
+        let mut captured_at = mv.end;
+        if is_pawn && mv.start.y != mv.end.y && self.board.is_empty(mv.end) {
+            // En passant, the captured pawn sits beside the start square instead of on the end
+            captured_at = Position {
+                x: mv.start.x,
+                y: mv.end.y,
+            };
+        }
+        // The victim leaves the board, which for en passant is the pawn beside the start square.
+        // A castle captures nothing, even when written as the king moving onto its rook
+        let captured = match rook_move {
+            Some(_) => None,
+            None => self.board.square(captured_at).map(|mut captured| {
+                captured.captured = true;
+                captured
+            }),
+        };
+        let record = MoveRecord {
+            mv,
+            moved: piece,
+            captured,
+            captured_at,
+            en_passant: self.en_passant,
+            castling: self.castling,
+            halfmove_clock: self.halfmove_clock,
+            hash: self.hash,
+        };
+        self.halfmove_clock = if is_pawn || captured.is_some() {
+            0
+        } else {
+            self.halfmove_clock + 1
+        };
+        if piece.color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.hash ^= zobrist::piece_key(piece, mv.start) ^ zobrist::en_passant_key(self.en_passant);
+        if let Some(captured) = captured {
+            self.hash ^= zobrist::piece_key(captured, captured_at);
+            self.material -= material_swing(captured);
+            self.board.clear(captured_at);
+        }
+        self.en_passant = if is_pawn && (mv.end.x - mv.start.x).abs() == 2 {
+            Some(Position {
+                x: (mv.start.x + mv.end.x) / 2,
+                y: mv.start.y,
+            })
+        } else {
+            None
+        };
+        let landed_at = match rook_move {
+            // Castling carries the rook over to the square beside the king's landing square
+            Some(rook_move) => {
+                let landing = castling::king_landing(rook_move);
+                let mut king = piece;
+                let rook = self.board.square(rook_move.start);
+                // Both pieces come off before either is put down, as in Chess960 either may
+                // land on the square the other started on
+                self.board.clear(mv.start);
+                self.board.clear(rook_move.start);
+                if let Some(mut rook) = rook {
+                    self.hash ^= zobrist::piece_key(rook, rook_move.start)
+                        ^ zobrist::piece_key(rook, rook_move.end);
+                    rook.first_move = false;
+                    rook.position = rook_move.end;
+                    self.board.place(rook);
+                }
+                king.first_move = false;
+                king.position = landing;
+                self.board.place(king);
+                landing
+            }
+            None => {
+                self.board.apply_move(mv);
+                mv.end
+            }
+        };
+        // The piece on the end square is not the one that left if a pawn promoted
+        let landed = self
+            .board
+            .square(landed_at)
+            .expect("the moved piece stands on the end square");
+        self.material += material_swing(landed) - material_swing(piece);
+        self.hash ^=
+            zobrist::piece_key(landed, landed_at) ^ zobrist::en_passant_key(self.en_passant);
+        self.hash ^= zobrist::castling_key(self.castling);
+        self.castling.remove_for_move(piece, mv.start);
+        if let Some(captured) = captured {
+            self.castling.remove_for_move(captured, captured_at);
+        }
+        self.hash ^= zobrist::castling_key(self.castling);
+        self.pass_turn();
+        record
+    }
+
+    /// Hands the move to the other player, keeping the hash in step
+    fn pass_turn(&mut self) {
+        self.turn = self.turn.opposite();
+        self.hash ^= zobrist::side_key();
+    }
+
+    /// Returns true if the king of the given color is attacked by any enemy piece
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let king = match self.board.find_king(color) {
+            Some(king) => king,
+            None => return false,
+        };
+        self.board.is_square_attacked(king, color.opposite())
+    }
+
+    /// Squares of the pieces of color `by` that attack `target`. Pawns only attack diagonally
+    /// forward, never the square they would push to
+    pub fn attackers_of(&self, target: Position, by: Color) -> Vec<Position> {
+        self.board
+            .iter_pieces()
+            .filter(|&(start, piece)| {
+                if piece.color != by {
+                    return false;
+                }
+                if piece.piece_type == PieceType::Pawn {
+                    let forward = match by {
+                        Color::Black => -1,
+                        _ => 1,
+                    };
+                    return target.x - start.x == forward && (target.y - start.y).abs() == 1;
+                }
+                self.is_pseudo_legal(Move {
+                    start,
+                    end: target,
+                    promotion: None,
+                })
+            })
+            .map(|(start, _)| start)
+            .collect()
+    }
+
+    /// Counts how many enemy pieces attack the piece on `square` and how many of its own pieces
+    /// defend it, as (attackers, defenders). An empty square gives (0, 0)
+    pub fn attack_defense(&self, square: Position) -> (usize, usize) {
+        let piece = match self.board.square(square) {
+            Some(piece) => piece,
+            None => return (0, 0),
+        };
+        let enemy = piece.color.opposite();
+        let attackers = self.attackers_of(square, enemy).len();
+
+        // A piece can never move onto its own side's piece, so defenders are counted as the
+        // attackers of an enemy piece standing on the square instead
+        let mut probe = self.clone();
+        probe
+            .board
+            .place(Piece::new(piece.piece_type, enemy, square));
+        let defenders = probe.attackers_of(square, piece.color).len();
+        (attackers, defenders)
+    }
+
+    /// Squares of the friendly pieces that the piece on `pos` defends, meaning it could recapture
+    /// on them if an enemy piece took them. Pawns defend diagonally forward. Empty for an empty
+    /// square
+    pub fn defended_squares(&self, pos: Position) -> Vec<Position> {
+        let defender = match self.board.square(pos) {
+            Some(defender) => defender,
+            None => return Vec::new(),
+        };
+        let enemy = defender.color.opposite();
+        // As in attack_defense, each friendly piece is swapped for an enemy one in turn so the
+        // defender is allowed to move onto its square
+        let mut probe = self.clone();
+        self.board
+            .iter_pieces()
+            .filter(|&(target, friend)| target != pos && friend.color == defender.color)
+            .filter(|&(target, friend)| {
+                probe
+                    .board
+                    .place(Piece::new(friend.piece_type, enemy, target));
+                let defended = probe.attackers_of(target, defender.color).contains(&pos);
+                probe.board.place(friend);
+                defended
+            })
+            .map(|(target, _)| target)
+            .collect()
+    }
+
+    /// Squares of the pieces giving check to the side to move, which is how the last move gave
+    /// check. A discovered check reports the piece that was uncovered rather than the one that
+    /// moved, and a double check reports both. Empty when not in check
+    pub fn checkers(&self) -> Vec<Position> {
+        let king = match self.board.find_king(self.turn) {
+            Some(king) => king,
+            None => return Vec::new(),
+        };
+        let enemy = self.turn.opposite();
+        self.attackers_of(king, enemy)
+    }
+
+    /// Every legal move for the side to move. Moves are generated per piece from its own square,
+    /// so a castle appears once, as a king move, and never as a separate rook move
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.generate_legal_moves().collect()
+    }
+
+    /// Every legal move `color` would have were it their turn to play, which for the side to
+    /// move is the same as legal_moves
+    pub fn all_legal_moves(&self, color: Color) -> Vec<Move> {
+        if color == self.turn {
+            return self.legal_moves();
+        }
+        self.with_other_side_to_move().legal_moves()
+    }
+
+    /// Every legal move for the side to move that ends on `target`
+    pub fn legal_moves_to(&self, target: Position) -> Vec<Move> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|mv| mv.end == target)
+            .collect()
+    }
+
+    /// Every legal move for the side to move that captures a piece, including en passant
+    pub fn legal_captures(&self) -> Vec<Move> {
+        self.generate_legal_moves_where(move |mv| self.is_capture(mv))
+            .collect()
+    }
+
+    /// Every legal move for `piece` from the square it stands on. Only the side to move has
+    /// legal moves, so a piece of the other color has none
+    pub fn find_legal_moves(&self, piece: Piece) -> Vec<Move> {
+        if piece.color != self.turn {
+            return Vec::new();
+        }
+        self.generate_legal_moves()
+            .filter(|mv| mv.start == piece.position)
+            .collect()
+    }
+
+    /// Returns true if the side to move has any legal move, stopping at the first one found
+    pub fn has_legal_moves(&self) -> bool {
+        self.generate_legal_moves().next().is_some()
+    }
+
+    /// Generates the legal moves lazily, so callers that only need to know whether there are any
+    /// can stop early
+    fn generate_legal_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.generate_legal_moves_where(|_| true)
+    }
+
+    /// Generates the legal moves that `wanted` picks out, checking whether each is legal only
+    /// once it has been picked
+    fn generate_legal_moves_where<'a>(
+        &'a self,
+        wanted: impl Fn(Move) -> bool + 'a,
+    ) -> impl Iterator<Item = Move> + 'a {
+        let evasion_targets = self.evasion_targets();
+        self.board
+            .occupied_squares()
+            .filter(move |&start| self.board.color_at(start) == Some(self.turn))
+            .flat_map(|start| {
+                all_squares().map(move |end| Move {
+                    start,
+                    end,
+                    promotion: None,
+                })
+            })
+            .filter(move |&mv| {
+                // While in check, only the king can go anywhere other than the evasion targets
+                if let Some(targets) = &evasion_targets {
+                    if self.board.piece_type_at(mv.start) != Some(PieceType::King)
+                        && !targets.contains(&mv.end)
+                    {
+                        return false;
+                    }
+                }
+                wanted(mv) && self.is_pseudo_legal(mv) && !self.leaves_king_in_check(mv)
+            })
+    }
+
+    /// When the side to move is in check, the squares a piece other than the king could move to
+    /// in order to deal with it: capturing the checker or, if the checker is a bishop, rook or
+    /// queen, interposing on a square between it and the king. Knight and pawn checks cannot be
+    /// blocked and nothing but a king move answers a double check. `None` when not in check
+    fn evasion_targets(&self) -> Option<Vec<Position>> {
+        let king = self.board.find_king(self.turn)?;
+        let enemy = self.turn.opposite();
+        let checkers = self.attackers_of(king, enemy);
+        match checkers.as_slice() {
+            [] => None,
+            [checker] => {
+                let mut targets = vec![*checker];
+                let checker_type = self.board.piece_type_at(*checker);
+                if checker_type.is_some_and(PieceType::is_sliding) {
+                    targets.extend(squares_between(king, *checker));
+                }
+                // A pawn that just advanced two squares can also be taken en passant
+                if checker_type == Some(PieceType::Pawn) {
+                    targets.extend(self.en_passant);
+                }
+                Some(targets)
+            }
+            _ => Some(Vec::new()),
+        }
+    }
+
+    /// Every legal move for the side to move that puts the enemy king in check
+    pub fn checking_moves(&self) -> Vec<Move> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|&mv| {
+                let mut trial = self.clone();
+                trial.apply_move(mv);
+                trial.is_in_check(trial.turn)
+            })
+            .collect()
+    }
+
+    /// Every legal move for the side to move after which the moved piece attacks two or more
+    /// enemy pieces worth more than itself, a simple test for forks. The enemy king counts as
+    /// worth more than anything, while a moving king is never worth less than what it attacks
+    pub fn forking_moves(&self) -> Vec<Move> {
+        let worth = |piece_type: PieceType| match piece_type {
+            PieceType::King => i32::MAX,
+            _ => piece_type.value(),
+        };
+        self.legal_moves()
+            .into_iter()
+            .filter(|&mv| {
+                let mover = match self.board.square(mv.start) {
+                    Some(mover) => mover,
+                    None => return false,
+                };
+                let mut trial = self.clone();
+                trial.apply_move(mv);
+                let targets = trial
+                    .board
+                    .iter_pieces()
+                    .filter(|(pos, piece)| {
+                        piece.color != mover.color
+                            && worth(piece.piece_type) > worth(mover.piece_type)
+                            && trial.attackers_of(*pos, mover.color).contains(&mv.end)
+                    })
+                    .count();
+                targets >= 2
+            })
+            .collect()
+    }
+
+    /// Returns true if the side to move can play `mv` and its king is not in check afterwards.
+    /// Only interesting while in check, when it tells which moves get out of it
+    pub fn move_escapes_check(&self, mv: Move) -> bool {
+        self.is_pseudo_legal(mv)
+            && self.board.color_at(mv.start) == Some(self.turn)
+            && !self.leaves_king_in_check(mv)
+    }
+
+    /// Plays `mv` on a copy of the game and reports whether the mover's king is then attacked.
+    /// Playing the move, rather than only checking the moving piece for pins, also removes a pawn
+    /// taken en passant, so a capture that empties both pawns off a rank guarded by an enemy rook
+    /// or queen is caught
+    fn leaves_king_in_check(&self, mv: Move) -> bool {
+        let mut trial = self.clone();
+        trial.apply_move(mv);
+        trial.is_in_check(self.turn)
+    }
+
+    /// Returns true if `color` has enough material to checkmate in some line of play. A lone king,
+    /// or a king with just one knight or one bishop, never can
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let mut minor_pieces = 0;
+        for (_, piece) in self.board.pieces(color) {
+            match piece.piece_type {
+                PieceType::Knight | PieceType::Bishop => minor_pieces += 1,
+                PieceType::Pawn | PieceType::Rook | PieceType::Queen => return true,
+                PieceType::King => {}
+            }
+        }
+        minor_pieces > 1
+    }
+
+    /// Returns true when neither side has enough material left to checkmate: two bare kings, a
+    /// single knight or bishop against a bare king, or one bishop each on squares of the same
+    /// color
+    pub fn is_insufficient_material(&self) -> bool {
+        let others: Vec<(Position, Piece)> = self
+            .board
+            .iter_pieces()
+            .filter(|(_, piece)| piece.piece_type != PieceType::King)
+            .collect();
+        match others.as_slice() {
+            [] => true,
+            [(_, piece)] => matches!(piece.piece_type, PieceType::Knight | PieceType::Bishop),
+            [(first_pos, first), (second_pos, second)] => {
+                // Squares are light or dark according to whether rank plus file is odd or even
+                let shade = |pos: &Position| (pos.x + pos.y) % 2;
+                first.piece_type == PieceType::Bishop
+                    && second.piece_type == PieceType::Bishop
+                    && first.color != second.color
+                    && shade(first_pos) == shade(second_pos)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true for endgames without pawns that are drawn with sensible play even though
+    /// mate is still possible on the board. Besides insufficient material, the recognized
+    /// endings are:
+    ///
+    /// - a knight or bishop against a bare king, or against a knight or bishop
+    /// - two knights against a bare king, which cannot force mate
+    /// - a rook against a rook
+    pub fn is_theoretical_draw(&self) -> bool {
+        if self.is_insufficient_material() {
+            return true;
+        }
+        // Knights, bishops, rooks, queens and pawns, in that order
+        let pieces = |color: Color| {
+            [
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+                PieceType::Pawn,
+            ]
+            .map(|piece_type| self.board.pieces_of(piece_type, color).len())
+        };
+        let bare = [0, 0, 0, 0, 0];
+        let knight = [1, 0, 0, 0, 0];
+        let bishop = [0, 1, 0, 0, 0];
+        let two_knights = [2, 0, 0, 0, 0];
+        let rook = [0, 0, 1, 0, 0];
+        let (white, black) = (pieces(Color::White), pieces(Color::Black));
+        let minor_or_bare = |side| side == knight || side == bishop || side == bare;
+        (minor_or_bare(white) && minor_or_bare(black))
+            || (white == two_knights && black == bare)
+            || (black == two_knights && white == bare)
+            || (white == rook && black == rook)
+    }
+
+    /// Returns true once the current position has occurred three times. Positions only count as
+    /// the same when the side to move, castling rights and en passant square match too, since
+    /// all of them are part of the Zobrist hash
+    pub fn is_threefold_repetition(&self) -> bool {
+        let current = match self.position_hashes.last() {
+            Some(current) => current,
+            None => return false,
+        };
+        let repetitions = self
+            .position_hashes
+            .iter()
+            .filter(|&hash| hash == current)
+            .count();
+        repetitions >= 3
+    }
+
+    /// A draw the side to move may claim, if any. When both a threefold repetition and the
+    /// fifty-move rule apply, the repetition is reported
+    pub fn can_claim_draw(&self) -> Option<DrawReason> {
+        if self.is_threefold_repetition() {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Claims a draw if one is available, ending the game, and returns the reason it was granted
+    pub fn claim_draw(&mut self) -> Option<DrawReason> {
+        self.claimed_draw = self.can_claim_draw();
+        self.claimed_draw
+    }
+
+    /// The outcome of the game if it has ended, or `None` while the side to move can still play.
+    /// Having no legal moves is checkmate when in check and stalemate otherwise. A threefold
+    /// repetition ends the game without anyone having to claim it
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(reason) = self.claimed_draw {
+            return Some(GameResult::Draw(reason));
+        }
+        if self.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
+        if self.is_insufficient_material() {
+            return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+        }
+        if self.has_legal_moves() {
+            return None;
+        }
+        if !self.is_in_check(self.turn) {
+            return Some(GameResult::Draw(DrawReason::Stalemate));
+        }
+        match self.turn {
+            Color::Black => Some(GameResult::WhiteWins),
+            _ => Some(GameResult::BlackWins),
+        }
+    }
+
+    /// Returns true if `color` is in check and has no legal move to get out of it
+    pub fn is_checkmate(&self, color: Color) -> bool {
+        self.is_in_check(color) && !self.can_move(color)
+    }
+
+    /// Returns true if `color` is not in check but has no legal move
+    pub fn is_stalemate(&self, color: Color) -> bool {
+        !self.is_in_check(color) && !self.can_move(color)
+    }
+
+    /// Returns true if `color` would have a legal move were it their turn to play
+    fn can_move(&self, color: Color) -> bool {
+        if color == self.turn {
+            return self.has_legal_moves();
+        }
+        self.with_other_side_to_move().has_legal_moves()
+    }
+
+    /// A copy of the game with the other side to move. The en passant square is dropped, since
+    /// only the side to move right after the pawn advanced could use it
+    fn with_other_side_to_move(&self) -> Game {
+        let mut other = self.clone();
+        other.hash ^= zobrist::en_passant_key(other.en_passant);
+        other.en_passant = None;
+        other.pass_turn();
+        other
+    }
+
+    /// Where the game stands for the side to move, for showing after every move. Stops looking
+    /// for legal moves as soon as it finds one, so it costs about as much as `result`
+    pub fn status(&self) -> GameStatus {
+        if let Some(reason) = self.claimed_draw {
+            return GameStatus::Draw(reason);
+        }
+        if self.is_threefold_repetition() {
+            return GameStatus::Draw(DrawReason::ThreefoldRepetition);
+        }
+        if self.is_insufficient_material() {
+            return GameStatus::Draw(DrawReason::InsufficientMaterial);
+        }
+        let in_check = self.is_in_check(self.turn);
+        let can_move = self.has_legal_moves();
+        match (in_check, can_move) {
+            (true, true) => GameStatus::Check,
+            (true, false) => GameStatus::Checkmate,
+            (false, true) => GameStatus::InProgress,
+            (false, false) => GameStatus::Stalemate,
+        }
+    }
+
+    /// Main game loop. Plays until the game ends and returns how it ended
+    pub fn run_game(&mut self) -> GameResult {
+        self.play_game(None)
+    }
+
+    /// Runs the game loop with `engine` choosing the moves for `color` while the other side
+    /// types theirs, and returns how the game ended
+    pub fn run_game_against(&mut self, engine: &dyn Engine, color: Color) -> GameResult {
+        self.play_game(Some((engine, color)))
+    }
+
+    /// Runs the game loop against the built-in engine, which plays `computer` and searches as
+    /// many plies deep as the difficulty setting
+    pub fn run_game_against_computer(&mut self, computer: Color) -> GameResult {
+        let engine = MinimaxEngine::new(self.difficulty);
+        self.play_game(Some((&engine, computer)))
+    }
+
+    /// How the game ends when `flagged` runs out of time: a loss, unless the opponent could
+    /// never checkmate, in which case it is a draw
+    pub fn flag_fall_result(&self, flagged: Color) -> GameResult {
+        let opponent = flagged.opposite();
+        if !self.has_mating_material(opponent) {
+            return GameResult::Draw(DrawReason::InsufficientMaterial);
+        }
+        match opponent {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+
+    /// The game loop behind run_game and run_game_against. With a clock set, each side's time
+    /// runs while it is to move, and a side that runs out loses on time
+    fn play_game(&mut self, engine: Option<(&dyn Engine, Color)>) -> GameResult {
+        // Time left for White and Black, in that order
+        let mut time_left = self.clock.map(|clock| [clock.initial; 2]);
+        let mut turn_started = Instant::now();
+        let mut plies = self.history.len();
+        loop {
+            if let (Some(clock), Some(time_left)) = (self.clock, time_left.as_mut()) {
+                // A move was played since the clock was last looked at, so charge its player.
+                // Taking moves back only restarts the clock
+                if self.history.len() > plies {
+                    let mover = self.turn.opposite();
+                    let left = &mut time_left[clock_index(mover)];
+                    let spent = turn_started.elapsed();
+                    if spent >= *left {
+                        let result = self.flag_fall_result(mover);
+                        match result {
+                            GameResult::Draw(_) => {
+                                println!("{:?} ran out of time, the game is a draw", mover)
+                            }
+                            _ => {
+                                println!("{:?} ran out of time, {:?} wins", mover, mover.opposite())
+                            }
+                        }
+                        return result;
+                    }
+                    *left = *left - spent + clock.increment;
+                }
+                if self.history.len() != plies {
+                    turn_started = Instant::now();
+                    plies = self.history.len();
+                }
+            }
+
+            self.print_board();
+            if let Some(time_left) = time_left {
+                println!(
+                    "White {} | Black {}",
+                    format_clock(time_left[clock_index(Color::White)]),
+                    format_clock(time_left[clock_index(Color::Black)])
+                );
+            }
+            println!();
+
+            // A board still being set up by hand has no result yet
+            let playable = self.validate_position();
+            if playable.is_ok() {
+                if let Some(result) = self.result() {
+                    println!("{}", result);
+                    return result;
+                }
+            }
+
+            if let Some((engine, color)) = engine {
+                if color == self.turn && playable.is_ok() {
+                    // The result check above means there is always a move to choose
+                    if let Some(mv) = engine.choose_move(self) {
+                        // Named from the position before the move, and only once it is known
+                        // to be legal
+                        let before = self.clone();
+                        match self.make_move(mv) {
+                            Ok(()) => {
+                                println!("{:?} plays {}", color, before.move_to_san(mv));
+                                continue;
+                            }
+                            // A broken engine hands the move over to be typed instead
+                            Err(err) => println!(
+                                "Engine move refused: {}",
+                                self.describe_rejection(mv, &err)
+                            ),
+                        }
+                    }
+                }
+            }
+
             // Get input for the current user
-            // |-> somehow call engine to make the move
             let mut user_input = String::new();
             match self.turn {
                 Color::White => print!("White move: "),
                 Color::Black => print!("Black move: "),
-                Color::Empty => print!("Should never happen, file a bug report for \"Empty turn\""),
             };
 
             // Flush the input here because, for reasons i'm not entirely sure of,
@@ -437,48 +2119,1480 @@ impl Game {
                 .read_line(&mut user_input)
                 .expect("Couldn't read input.");
 
-            // Parse user input and translate it to a movement
-            // |-> Helper function to check legality of parsed move
-            self.parse_move(&user_input);
+            match self.run_command(&user_input) {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(err) => {
+                    println!("{}", err);
+                    continue;
+                }
+            }
+            // Play only resumes once the position set up with the commands makes sense
+            if let Err(err) = playable {
+                println!("Cannot play from this position: {}", err);
+                continue;
+            }
 
-            // Make user move and update board state and turn, check for check / mate
-            match self.turn {
-                Color::White => self.turn = Color::Black,
-                Color::Black => self.turn = Color::White,
-                Color::Empty => self.turn = Color::Empty,
-            }
-        }
-    }
-
-    /// Parse a PGN move aka: Algebraic notation
-    fn parse_move(&self, mut user_move_string: &String) {
-        let color = self.turn;
-
-        let moving_piece_str = user_move_string.chars().rev().nth(0).unwrap();
-        // need the unwrap to allow for a single match rather than a nested
-        let piece_type: PieceType = match moving_piece_str {
-            'p' => PieceType::Pawn,
-            'P' => PieceType::Pawn,
-            'r' => PieceType::Rook,
-            'R' => PieceType::Rook,
-            'n' => PieceType::Knight,
-            'B' => PieceType::Bishop,
-            'b' => PieceType::Bishop,
-            'k' => PieceType::King,
-            'K' => PieceType::King,
-            'Q' => PieceType::Queen,
-            'q' => PieceType::Queen,
-            _ => PieceType::None,
+            // A move that cannot be played leaves the turn with the same player, who is asked
+            // again
+            if let Err(message) = self.play_typed_move(&user_input) {
+                println!("{}", message);
+            }
+        }
+    }
+
+    /// Plays a move typed by the player, in algebraic or coordinate notation. Nothing changes,
+    /// and the turn does not pass, unless the move can be read and is legal. Otherwise returns
+    /// the message to show the player
+    pub fn play_typed_move(&mut self, input: &str) -> Result<(), String> {
+        let input = input.trim();
+        // Coordinate notation, like "e2e4", is still read so that a refused move can be
+        // explained. Anything else is read as algebraic notation
+        let mv = match parse_coordinate_move(input) {
+            Ok(mv) => mv,
+            Err(_) => match self.parse_move(input) {
+                Ok(mv) => mv,
+                // Algebraic moves that match no legal move are explained the same way
+                Err(err @ ParseError::NoMatchingMove(_)) => {
+                    return Err(match self.refused_san_move(input) {
+                        Some((mv, error)) => self.describe_rejection(mv, &error),
+                        None => err.to_string(),
+                    })
+                }
+                Err(err) => return Err(err.to_string()),
+            },
         };
+        self.make_move(mv)
+            .map_err(|err| self.describe_rejection(mv, &err))
+    }
+
+    /// Plays moves read one per line in coordinate notation, such as "e2e4", until the end of
+    /// the input, skipping blank lines. Stops at the first line that is not a legal move or
+    /// cannot be read and reports its line number, keeping the moves before it
+    pub fn run_from_reader(&mut self, reader: impl BufRead) -> Result<(), ReplayError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|error| ReplayError::Io {
+                line: index + 1,
+                error,
+            })?;
+            let text = line.trim();
+            if text.is_empty() {
+                continue;
+            }
+            parse_coordinate_move(text)
+                .and_then(|mv| self.make_move(mv))
+                .map_err(|error| ReplayError::IllegalMove {
+                    line: index + 1,
+                    error,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// The board as it stands, for inspecting the position
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The side whose move it is
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    /// Number of the move being played, starting at 1 and going up once Black has moved, as
+    /// written in FEN and before moves in PGN
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Plies since the last capture or pawn move, the count the fifty-move rule goes by
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Square a pawn skipped over on the last move by advancing two squares, where an enemy
+    /// pawn beside it may capture it en passant. `None` after any other move
+    pub fn en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    /// How many plies deep the engine searches in run_game_against_computer
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// Characters the board is printed with
+    pub fn charset(&self) -> RenderStyle {
+        self.charset
+    }
+
+    /// Returns true if the board is printed from the other side
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// Time each player gets, or `None` for a game without a clock
+    pub fn clock(&self) -> Option<TimeControl> {
+        self.clock
     }
 
     /// Print the game board to the console
     pub fn print_board(&self) {
-        for rank in 0..BOARD_DIMENSIONS {
-            for file in 0..BOARD_DIMENSIONS {
-                print!("{}", self.board.state[rank][file].board_rep)
+        debug_assert!(
+            self.board.positions_in_sync(),
+            "a piece's position does not match its square"
+        );
+        let pov = if self.flipped {
+            Color::Black
+        } else {
+            Color::White
+        };
+        print!("{}", self.board.draw(self.charset, pov));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move {
+            start: sq(from),
+            end: sq(to),
+            promotion: None,
+        }
+    }
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            game.make_uci_move(uci)
+                .unwrap_or_else(|err| panic!("{}: {}", uci, err));
+        }
+    }
+
+    #[test]
+    fn capture_target_names_the_victim() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "d7d5"]);
+        assert_eq!(
+            game.capture_target(mv("e4", "d5")),
+            Some((PieceType::Pawn, Color::Black))
+        );
+        assert_eq!(game.capture_target(mv("e4", "e5")), None);
+        assert_eq!(game.capture_target(mv("d1", "d2")), None);
+        assert_eq!(game.capture_target(mv("e3", "e4")), None);
+
+        // En passant takes the pawn beside the mover, not anything on the empty landing square
+        play(&mut game, &["e4e5", "f7f5"]);
+        assert_eq!(
+            game.capture_target(mv("e5", "f6")),
+            Some((PieceType::Pawn, Color::Black))
+        );
+    }
+
+    #[test]
+    fn undo_restores_everything_a_capture_changed() {
+        let mut game = Game::new();
+        for mv in ["e2e4", "d7d5", "g1f3", "b8c6"] {
+            game.make_uci_move(mv).unwrap();
+        }
+        let before = game.clone();
+        game.make_uci_move("e4d5").unwrap();
+        assert_eq!(game.undo(), Some(parse_coordinate_move("e4d5").unwrap()));
+
+        for pos in all_squares() {
+            let fields = |board: &Board| {
+                board.square(pos).map(|piece| {
+                    (
+                        piece.piece_type,
+                        piece.color,
+                        piece.first_move,
+                        piece.captured,
+                        piece.position,
+                    )
+                })
+            };
+            assert_eq!(fields(&game.board), fields(&before.board), "{:?}", pos);
+        }
+        assert_eq!(game.turn, before.turn);
+        assert_eq!(game.castling, before.castling);
+        assert_eq!(game.en_passant, before.en_passant);
+        assert_eq!(game.halfmove_clock, before.halfmove_clock);
+        assert_eq!(game.fullmove_number, before.fullmove_number);
+        assert_eq!(game.material, before.material);
+        assert_eq!(game.hash, before.hash);
+        assert_eq!(game.position_hashes, before.position_hashes);
+        assert_eq!(game.history.len(), before.history.len());
+        assert_eq!(game.to_fen(), before.to_fen());
+
+        while game.undo().is_some() {}
+        assert_eq!(game.to_fen(), Game::new().to_fen());
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn running_out_of_time_loses_unless_the_opponent_cannot_mate() {
+        let game = Game::new();
+        assert_eq!(game.flag_fall_result(Color::White), GameResult::BlackWins);
+        assert_eq!(game.flag_fall_result(Color::Black), GameResult::WhiteWins);
+        let lone_knight = Game::from_fen("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1").unwrap();
+        assert_eq!(
+            lone_knight.flag_fall_result(Color::Black),
+            GameResult::Draw(DrawReason::InsufficientMaterial)
+        );
+        assert_eq!(
+            lone_knight.flag_fall_result(Color::White),
+            GameResult::Draw(DrawReason::InsufficientMaterial)
+        );
+        assert_eq!(format_clock(Duration::from_secs(245)), "4:05");
+    }
+
+    #[test]
+    fn replays_moves_from_a_reader() {
+        let mut game = Game::new();
+        game.run_from_reader("e2e4\n\ne7e5\ng1f3\n".as_bytes())
+            .unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        let mut game = Game::new();
+        match game.run_from_reader("e2e4\ne7e5\ne4e5\n".as_bytes()) {
+            Err(ReplayError::IllegalMove { line: 3, .. }) => {}
+            other => panic!("expected line 3 to be refused, got {:?}", other),
+        }
+        assert_eq!(game.history.len(), 2);
+    }
+
+    #[test]
+    fn replay_reports_read_errors() {
+        let mut game = Game::new();
+        let input: &[u8] = b"e2e4\n\xff\xfe\ne7e5\n";
+        match game.run_from_reader(input) {
+            Err(ReplayError::Io { line: 2, .. }) => {}
+            other => panic!("expected line 2 to fail to read, got {:?}", other),
+        }
+        assert_eq!(game.history.len(), 1);
+    }
+
+    #[test]
+    fn result_tells_checkmate_from_stalemate() {
+        let mut game = Game::new();
+        assert_eq!(game.result(), None);
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(game.result(), Some(GameResult::BlackWins));
+        assert_eq!(game.result().unwrap().to_string(), "Checkmate, Black wins");
+
+        let stalemate = Game::from_fen("k7/8/1QK5/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            stalemate.result(),
+            Some(GameResult::Draw(DrawReason::Stalemate))
+        );
+        assert_eq!(
+            stalemate.result().unwrap().to_string(),
+            "Stalemate, the game is a draw"
+        );
+    }
+
+    #[test]
+    fn mirrored_swaps_the_sides() {
+        let start = Game::new().board;
+        assert!(start.mirrored() == start);
+
+        let board = Game::from_fen("rnbqkbnr/ppp1pppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap()
+            .board;
+        let mirrored = board.mirrored();
+        assert!(mirrored.square(sq("d2")).is_none());
+        assert_eq!(mirrored.color_at(sq("d7")), Some(Color::Black));
+        assert_eq!(mirrored.square(sq("e1")).unwrap().board_rep(), 'K');
+        assert_eq!(mirrored.square(sq("e1")).unwrap().position, sq("e1"));
+        assert!(mirrored != board);
+        assert!(mirrored.mirrored() == board);
+    }
+
+    #[test]
+    fn open_and_half_open_files() {
+        let board = Game::new().board;
+        assert!(!board.is_open_file(4));
+        assert!(!board.is_half_open_file(4, Color::White));
+
+        let board = Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap()
+            .board;
+        assert!(board.is_half_open_file(4, Color::White));
+        assert!(!board.is_half_open_file(4, Color::Black));
+        assert!(!board.is_open_file(4));
+
+        let board = Game::from_fen("rnbqkbnr/pppp1ppp/8/8/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap()
+            .board;
+        assert!(board.is_open_file(4));
+        assert!(!board.is_open_file(3));
+    }
+
+    #[test]
+    fn bare_kings_end_the_game_at_once() {
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+        );
+        assert!(Game::new().result().is_none());
+
+        // Taking the last piece leaves the kings alone
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+        assert!(game.result().is_none());
+        play(&mut game, &["e1d2"]);
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn checking_moves_lists_every_check() {
+        assert!(Game::new().checking_moves().is_empty());
+
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "f7f6", "d1e2", "a7a6"]);
+        let checks = game.checking_moves();
+        assert!(checks.contains(&mv("e2", "h5")));
+        assert!(!checks.contains(&mv("e2", "g4")));
+        for check in checks {
+            let mut after = game.clone();
+            after.make_move(check).unwrap();
+            assert!(after.is_in_check(Color::Black), "{}", check.to_uci());
+        }
+    }
+
+    #[test]
+    fn mating_material_for_one_side() {
+        let bare = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!bare.has_mating_material(Color::White));
+        let knight = Game::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!knight.has_mating_material(Color::White));
+        let rook = Game::from_fen("4k3/8/8/8/3R4/8/8/4K3 w - - 0 1").unwrap();
+        assert!(rook.has_mating_material(Color::White));
+        assert!(!rook.has_mating_material(Color::Black));
+        let pawn = Game::from_fen("4k3/8/8/8/8/8/p7/4K3 w - - 0 1").unwrap();
+        assert!(pawn.has_mating_material(Color::Black));
+        assert!(Game::new().has_mating_material(Color::Black));
+    }
+
+    #[test]
+    fn squares_between_lines_up_two_squares() {
+        assert_eq!(
+            squares_between(sq("a1"), sq("a4")),
+            vec![sq("a2"), sq("a3")]
+        );
+        assert_eq!(
+            squares_between(sq("h8"), sq("e5")),
+            vec![sq("g7"), sq("f6")]
+        );
+        assert_eq!(
+            squares_between(sq("f1"), sq("c1")),
+            vec![sq("e1"), sq("d1")]
+        );
+        assert!(squares_between(sq("a1"), sq("b3")).is_empty());
+        assert!(squares_between(sq("a1"), sq("a2")).is_empty());
+        assert!(squares_between(sq("a1"), sq("a1")).is_empty());
+    }
+
+    #[test]
+    fn move_escapes_check_only_for_real_escapes() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "f7f6", "d1h5"]);
+        assert!(game.is_in_check(Color::Black));
+        assert!(game.move_escapes_check(mv("g7", "g6")));
+        assert!(!game.move_escapes_check(mv("a7", "a6")));
+        assert!(!game.move_escapes_check(mv("e8", "f7")));
+        // A White move is never an escape for Black
+        assert!(!game.move_escapes_check(mv("h5", "g6")));
+    }
+
+    #[test]
+    fn capture_and_castle_predicates() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "d7d5"]);
+        assert!(game.is_capture(mv("e4", "d5")));
+        assert!(!game.is_capture(mv("e4", "e5")));
+        assert!(game.is_castle(mv("e1", "g1")));
+        assert!(!game.is_castle(mv("e1", "e2")));
+        play(&mut game, &["e4e5", "f7f5"]);
+        assert!(game.is_capture(mv("e5", "f6")));
+
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b kq - 0 1").unwrap();
+        assert!(game.is_castle(mv("e8", "c8")));
+        assert!(!game.is_castle(mv("e1", "g1")));
+    }
+
+    #[test]
+    fn squares_run_rank_by_rank_from_a1() {
+        let squares = Game::new().board.squares();
+        assert_eq!(squares[0].unwrap().piece_type(), PieceType::Rook);
+        assert_eq!(squares[4].unwrap().piece_type(), PieceType::King);
+        assert_eq!(squares[8].unwrap().piece_type(), PieceType::Pawn);
+        assert_eq!(squares[60].unwrap().color(), Color::Black);
+        assert!(squares[30].is_none());
+    }
+
+    #[test]
+    fn piece_at_square_reads_algebraic_squares() {
+        let game = Game::new();
+        assert_eq!(
+            game.piece_at_square("e1"),
+            Ok(Some((PieceType::King, Color::White)))
+        );
+        assert_eq!(
+            game.piece_at_square("d8"),
+            Ok(Some((PieceType::Queen, Color::Black)))
+        );
+        assert_eq!(game.piece_at_square("e4"), Ok(None));
+        assert!(game.piece_at_square("z9").is_err());
+        assert!(game.piece_at_square("e").is_err());
+        assert!(game.piece_at_square("é1").is_err());
+    }
+
+    #[test]
+    fn evasions_block_sliders_but_not_knights() {
+        // The rook on e8 checks down the e-file, so the bishop may only block on it
+        let game = Game::from_fen("k3r3/8/8/8/8/8/2B5/4K3 w - - 0 1").unwrap();
+        let moves = game.legal_moves();
+        assert!(moves.contains(&mv("c2", "e4")));
+        assert!(!moves.contains(&mv("c2", "d3")));
+        assert!(moves.iter().all(|m| m.start == sq("e1") || m.end.y == 4));
+
+        // A knight check can only be answered by taking the knight or moving the king
+        let game = Game::from_fen("k7/8/8/8/8/3n4/8/R3K3 w - - 0 1").unwrap();
+        assert!(game
+            .legal_moves()
+            .iter()
+            .all(|m| m.start == sq("e1") || m.end == sq("d3")));
+    }
+
+    #[test]
+    fn evasion_filter_agrees_with_a_full_search() {
+        let mut rng = crate::rng::XorShiftRng::new(9);
+        for _ in 0..300 {
+            let game = Game::random_position(&mut rng);
+            let mut every = Vec::new();
+            for (start, _) in game.board.pieces(game.turn) {
+                for end in all_squares() {
+                    let mv = Move {
+                        start,
+                        end,
+                        promotion: None,
+                    };
+                    if game.is_pseudo_legal(mv) && !game.leaves_king_in_check(mv) {
+                        every.push(mv);
+                    }
+                }
             }
-            println!();
+            let mut generated = game.legal_moves();
+            every.sort_by_key(|mv| mv.to_uci());
+            generated.sort_by_key(|mv| mv.to_uci());
+            assert_eq!(generated, every, "{}", game.to_fen());
+        }
+    }
+
+    #[test]
+    fn sliders_never_wrap_around_the_board() {
+        let mut game = Game::from_fen("4k3/8/8/8/7R/8/8/4K2B w - - 0 1").unwrap();
+        let moves = game.legal_moves();
+        assert!(!moves.contains(&mv("h4", "a5")));
+        assert!(!moves.contains(&mv("h1", "a1")));
+        assert!(!moves.contains(&mv("h1", "a2")));
+        assert!(moves.contains(&mv("h4", "a4")));
+        for end in [Position { x: 3, y: 8 }, Position { x: -1, y: 7 }] {
+            let off_board = Move {
+                start: sq("h4"),
+                end,
+                promotion: None,
+            };
+            assert_eq!(game.make_move(off_board), Err(MoveError::IllegalMove));
+        }
+    }
+
+    #[test]
+    fn threefold_repetition_wins_over_the_fifty_move_rule() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let mut game =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 96 49").unwrap();
+        play(&mut game, &shuffle);
+        play(&mut game, &shuffle);
+        assert_eq!(game.halfmove_clock(), 104);
+        assert!(game.is_threefold_repetition());
+        assert_eq!(game.can_claim_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(game.claim_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(DrawReason::ThreefoldRepetition))
+        );
+
+        game.claimed_draw = None;
+        game.unmake_move();
+        assert_eq!(game.can_claim_draw(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(game.result(), None);
+
+        let mut game = Game::new();
+        play(&mut game, &shuffle);
+        assert_eq!(game.can_claim_draw(), None);
+    }
+
+    #[test]
+    fn piece_type_values_and_sliders() {
+        let sliding: Vec<PieceType> = [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ]
+        .iter()
+        .copied()
+        .filter(|piece_type| piece_type.is_sliding())
+        .collect();
+        assert_eq!(
+            sliding,
+            vec![PieceType::Bishop, PieceType::Rook, PieceType::Queen]
+        );
+        assert_eq!(PieceType::Queen.value(), 900);
+        assert_eq!(PieceType::Knight.value(), PieceType::Bishop.value());
+        assert_eq!(PieceType::King.value(), 0);
+    }
+
+    #[test]
+    fn checkers_lists_every_piece_giving_check() {
+        let mut game = Game::from_fen("4k3/8/8/8/4N3/8/8/4R2K w - - 0 1").unwrap();
+        assert!(game.checkers().is_empty());
+        play(&mut game, &["e4c5"]);
+        assert_eq!(game.checkers(), vec![sq("e1")]);
+        game.unmake_move();
+        // The knight checks too, so this is a double check
+        play(&mut game, &["e4d6"]);
+        let mut checkers = game.checkers();
+        checkers.sort_by_key(|pos| pos.to_algebraic());
+        assert_eq!(checkers, vec![sq("d6"), sq("e1")]);
+    }
+
+    #[test]
+    fn attack_defense_counts_both_sides() {
+        // The d6 pawn and the g6 knight attack e5, and only the d4 pawn defends it
+        let game = Game::from_fen("4k3/8/3p2n1/4P3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.attack_defense(sq("e5")), (2, 1));
+        assert_eq!(game.attack_defense(sq("d6")), (1, 0));
+        assert_eq!(game.attack_defense(sq("a3")), (0, 0));
+        assert_eq!(Game::new().attack_defense(sq("e2")), (0, 4));
+    }
+
+    #[test]
+    fn castling_is_generated_once_as_a_king_move() {
+        // The king has five steps and the castle, the rook seven squares up and two across
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let moves = game.legal_moves();
+        assert_eq!(moves.len(), 5 + 1 + 7 + 2);
+        assert_eq!(game.perft(1), moves.len() as u64);
+        assert_eq!(moves.iter().filter(|&&mv| game.is_castle(mv)).count(), 1);
+        assert!(moves.contains(&mv("e1", "g1")));
+        assert!(!moves.contains(&mv("h1", "e1")));
+
+        let without_rights = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        assert_eq!(without_rights.legal_moves().len(), moves.len() - 1);
+    }
+
+    #[test]
+    fn en_passant_cannot_uncover_a_rank_pin() {
+        // Taking en passant lifts both pawns off the fifth rank, opening it to the h5 rook
+        let game = Game::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 2").unwrap();
+        assert!(!game.legal_moves().contains(&mv("b5", "c6")));
+        assert!(game.legal_moves().contains(&mv("b5", "b6")));
+        assert_eq!(
+            game.clone().make_move(mv("b5", "c6")),
+            Err(MoveError::LeavesKingInCheck)
+        );
+
+        let unpinned = Game::from_fen("8/8/8/KPp5/8/8/8/4k3 w - c6 0 2").unwrap();
+        assert!(unpinned.legal_moves().contains(&mv("b5", "c6")));
+    }
+
+    #[test]
+    fn status_reports_checks_mates_and_draws() {
+        assert_eq!(Game::new().status(), GameStatus::InProgress);
+        let check = Game::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(check.status(), GameStatus::Check);
+        let mate = Game::from_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(mate.status(), GameStatus::Checkmate);
+        let stalemate = Game::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(stalemate.status(), GameStatus::Stalemate);
+        let bare_kings = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            bare_kings.status(),
+            GameStatus::Draw(DrawReason::InsufficientMaterial)
+        );
+    }
+
+    #[test]
+    fn make_uci_move_parses_and_plays() {
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        game.make_uci_move("e7e5").unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert_eq!(game.make_uci_move("e1e3"), Err(MoveError::IllegalMove));
+        assert!(matches!(
+            game.make_uci_move("e2"),
+            Err(MoveError::Malformed(_))
+        ));
+        assert_eq!(game.make_uci_move("d2d4q"), Err(MoveError::IllegalMove));
+
+        let mut game = Game::from_fen("K7/4P3/8/8/8/8/8/7k w - - 0 1").unwrap();
+        assert!(matches!(
+            game.make_uci_move("e7e8x"),
+            Err(MoveError::Malformed(_))
+        ));
+        game.make_uci_move("e7e8n").unwrap();
+        assert_eq!(
+            game.board.square(sq("e8")).map(|piece| piece.piece_type),
+            Some(PieceType::Knight)
+        );
+    }
+
+    #[test]
+    fn no_move_can_be_made_once_the_game_is_over() {
+        let mut mate = Game::from_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(!mate.has_legal_moves());
+        assert_eq!(mate.make_move(mv("e8", "e7")), Err(MoveError::GameOver));
+        assert_eq!(mate.make_uci_move("e8f8"), Err(MoveError::GameOver));
+
+        let mut stalemate = Game::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            stalemate.make_move(mv("a8", "b8")),
+            Err(MoveError::GameOver)
+        );
+
+        assert!(Game::new().has_legal_moves());
+        assert_eq!(
+            Game::new().make_move(mv("e2", "e5")),
+            Err(MoveError::IllegalMove)
+        );
+    }
+
+    #[test]
+    fn legal_moves_to_finds_every_piece_reaching_a_square() {
+        let game = Game::from_fen("4k3/8/8/8/8/2N5/4P3/4K3 w - - 0 1").unwrap();
+        let moves = game.legal_moves_to(sq("e4"));
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&mv("c3", "e4")) && moves.contains(&mv("e2", "e4")));
+        assert!(game.legal_moves_to(sq("e5")).is_empty());
+    }
+
+    #[test]
+    fn defended_squares_lists_the_friends_a_piece_covers() {
+        let game = Game::from_fen("4k3/8/8/3B1R2/8/4N3/2P5/4K3 w - - 0 1").unwrap();
+        let mut defended = game.defended_squares(sq("e3"));
+        defended.sort_by_key(|pos| (pos.x, pos.y));
+        assert_eq!(defended, vec![sq("c2"), sq("d5"), sq("f5")]);
+
+        // The queen covers the king along the first rank, but neither the knight nor the rook
+        let game = Game::from_fen("4k3/8/8/8/8/3N1R2/8/2Q1K3 w - - 0 1").unwrap();
+        let defended = game.defended_squares(sq("c1"));
+        assert_eq!(defended, vec![sq("e1")]);
+
+        let game = Game::from_fen("4k3/8/8/8/8/3N4/2P5/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.defended_squares(sq("c2")), vec![sq("d3")]);
+        assert!(game.defended_squares(sq("a5")).is_empty());
+    }
+
+    #[test]
+    fn theoretical_draws_are_recognized() {
+        let drawn = |fen: &str| Game::from_fen(fen).unwrap().is_theoretical_draw();
+        assert!(drawn("4k2r/8/8/8/8/8/8/R3K3 w - - 0 1"));
+        assert!(drawn("4k3/8/8/8/8/8/8/1N2K1N1 w - - 0 1"));
+        assert!(drawn("4kb2/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+        assert!(drawn("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(!drawn("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+        assert!(!drawn("4k2r/8/8/8/8/8/P7/R3K3 w - - 0 1"));
+        assert!(!drawn("4k3/8/8/8/8/8/8/1N2KBN1 w - - 0 1"));
+        assert!(!Game::new().is_theoretical_draw());
+    }
+
+    #[test]
+    fn pieces_of_finds_each_type_and_color() {
+        let board = Game::new().board;
+        let pawns: Vec<Position> = (0..8).map(|y| Position { x: 1, y }).collect();
+        assert_eq!(board.pieces_of(PieceType::Pawn, Color::White), pawns);
+        assert_eq!(
+            board.pieces_of(PieceType::Knight, Color::Black),
+            vec![sq("b8"), sq("g8")]
+        );
+        assert_eq!(
+            board.pieces_of(PieceType::Queen, Color::Black),
+            vec![sq("d8")]
+        );
+        assert_eq!(board.iter_pieces().count(), 32);
+        let empty = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1")
+            .unwrap()
+            .board;
+        assert!(empty.pieces_of(PieceType::Pawn, Color::White).is_empty());
+    }
+
+    #[test]
+    fn forking_moves_find_a_knight_fork() {
+        // Nc3 attacks both the king on e4 and the rook on a4
+        let game = Game::from_fen("7K/8/8/8/r3k3/8/8/1N6 w - - 0 1").unwrap();
+        assert_eq!(game.forking_moves(), vec![mv("b1", "c3")]);
+        // A single attacked piece is no fork
+        let game = Game::from_fen("7K/8/8/8/4k3/8/8/1N6 w - - 0 1").unwrap();
+        assert!(game.forking_moves().is_empty());
+        assert!(Game::new().forking_moves().is_empty());
+    }
+
+    #[test]
+    fn every_starting_bishop_moves_diagonally() {
+        // The starting bishops with the pawns lifted off so they have room to move
+        let mut board = Game::new().board;
+        for y in 0..BOARD_DIMENSIONS as i8 {
+            board.clear(Position { x: 1, y });
+            board.clear(Position { x: 6, y });
         }
+        let bishops: Vec<(Position, Piece)> = board
+            .iter_pieces()
+            .filter(|(_, piece)| piece.board_rep() == 'B' || piece.board_rep() == 'b')
+            .collect();
+        assert_eq!(bishops.len(), 4);
+        for (start, bishop) in bishops {
+            let forward = if bishop.color == Color::White { 2 } else { -2 };
+            let diagonal = Move {
+                start,
+                end: Position {
+                    x: start.x + forward,
+                    y: start.y + 2,
+                },
+                promotion: None,
+            };
+            let straight = Move {
+                start,
+                end: Position {
+                    x: start.x + forward,
+                    y: start.y,
+                },
+                promotion: None,
+            };
+            assert!(board.is_pseudo_legal(bishop, diagonal), "{:?}", start);
+            assert!(!board.is_pseudo_legal(bishop, straight), "{:?}", start);
+        }
+    }
+
+    #[test]
+    fn kings_step_one_square_in_any_direction() {
+        let board = Game::from_fen("4k3/8/8/8/3K4/8/8/8 w - - 0 1")
+            .unwrap()
+            .board;
+        let king = board.square(sq("d4")).unwrap();
+        for end in ["c3", "c4", "c5", "d3", "d5", "e3", "e4", "e5"] {
+            assert!(board.is_pseudo_legal(king, mv("d4", end)), "{}", end);
+        }
+        for end in ["f4", "b4", "d6", "d2", "b2", "f6", "d4"] {
+            assert!(!board.is_pseudo_legal(king, mv("d4", end)), "{}", end);
+        }
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.legal_moves().len(), 5);
+        assert_eq!(game.make_move(mv("e1", "e8")), Err(MoveError::IllegalMove));
+    }
+
+    #[test]
+    fn pawns_capture_diagonally_forward_only() {
+        let board = Game::from_fen("4k3/8/8/3p1P2/4P3/3n4/8/4K3 w - - 0 1")
+            .unwrap()
+            .board;
+        let pawn = board.square(sq("e4")).unwrap();
+        assert!(board.is_pseudo_legal(pawn, mv("e4", "d5")));
+        // Not onto a friend, not onto an empty square and not backwards
+        assert!(!board.is_pseudo_legal(pawn, mv("e4", "f5")));
+        assert!(!board.is_pseudo_legal(pawn, mv("e4", "f3")));
+        assert!(!board.is_pseudo_legal(pawn, mv("e4", "d3")));
+        assert!(board.is_pseudo_legal(pawn, mv("e4", "e5")));
+
+        let black = board.square(sq("d5")).unwrap();
+        assert!(board.is_pseudo_legal(black, mv("d5", "e4")));
+        assert!(!board.is_pseudo_legal(black, mv("d5", "c4")));
+    }
+
+    #[test]
+    fn en_passant_takes_the_pawn_that_just_passed() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "a7a6", "e4e5", "d7d5"]);
+        assert_eq!(game.en_passant(), Some(sq("d6")));
+        assert!(game.legal_moves().contains(&mv("e5", "d6")));
+
+        game.make_uci_move("e5d6").unwrap();
+        assert!(game.board.square(sq("d5")).is_none());
+        assert_eq!(
+            game.board.square(sq("d6")).map(|piece| piece.piece_type),
+            Some(PieceType::Pawn)
+        );
+        let record = game.history.last().unwrap();
+        let victim = record.captured.unwrap();
+        assert!(victim.captured);
+        assert_eq!(
+            (victim.piece_type, victim.color),
+            (PieceType::Pawn, Color::Black)
+        );
+        assert_eq!(record.captured_at, sq("d5"));
+        assert_eq!(game.en_passant(), None);
+
+        game.unmake_move();
+        let restored = game.board.square(sq("d5")).unwrap();
+        assert!(!restored.captured && restored.color == Color::Black);
+
+        // The chance is gone once another move is played
+        play(&mut game, &["b1c3", "a6a5"]);
+        assert!(!game.legal_moves().contains(&mv("e5", "d6")));
+    }
+
+    #[test]
+    fn rooks_stop_at_the_first_piece_in_their_path() {
+        let game = Game::from_fen("4k3/8/8/8/R2p3r/8/8/R3K3 w - - 0 1").unwrap();
+        let board = &game.board;
+        let rook = board.square(sq("a1")).unwrap();
+        // A friend on a4 can be neither taken nor passed
+        assert!(board.is_pseudo_legal(rook, mv("a1", "a3")));
+        assert!(!board.is_pseudo_legal(rook, mv("a1", "a4")));
+        assert!(!board.is_pseudo_legal(rook, mv("a1", "a6")));
+        // An enemy on d4 can be taken but not passed
+        let rook = board.square(sq("a4")).unwrap();
+        assert!(board.is_pseudo_legal(rook, mv("a4", "d4")));
+        assert!(!board.is_pseudo_legal(rook, mv("a4", "h4")));
+        // The a-file above is open
+        assert!(board.is_pseudo_legal(rook, mv("a4", "a8")));
+        assert_eq!(
+            game.clone().make_move(mv("a4", "e4")),
+            Err(MoveError::PathBlocked)
+        );
+    }
+
+    #[test]
+    fn bishops_stop_at_the_first_piece_on_the_diagonal() {
+        let game = Game::from_fen("4k3/8/8/8/3p4/8/1P6/2B1K3 w - - 0 1").unwrap();
+        let board = &game.board;
+        let bishop = board.square(sq("c1")).unwrap();
+        assert!(!board.is_pseudo_legal(bishop, mv("c1", "b2")));
+        assert!(!board.is_pseudo_legal(bishop, mv("c1", "a3")));
+        assert!(board.is_pseudo_legal(bishop, mv("c1", "h6")));
+        assert_eq!(
+            game.clone().make_move(mv("c1", "a3")),
+            Err(MoveError::PathBlocked)
+        );
+
+        let game = Game::from_fen("4k3/8/8/8/3B4/8/1p6/4K3 w - - 0 1").unwrap();
+        let bishop = game.board.square(sq("d4")).unwrap();
+        assert!(game.board.is_pseudo_legal(bishop, mv("d4", "b2")));
+        assert!(!game.board.is_pseudo_legal(bishop, mv("d4", "a1")));
+    }
+
+    #[test]
+    fn queens_are_blocked_like_rooks_and_bishops() {
+        let game = Game::from_fen("4k3/8/8/8/3p4/8/1P6/Q2pK3 w - - 0 1").unwrap();
+        let board = &game.board;
+        let queen = board.square(sq("a1")).unwrap();
+        // Along the first rank the pawn on d1 can be taken but not passed
+        assert!(board.is_pseudo_legal(queen, mv("a1", "d1")));
+        assert!(!board.is_pseudo_legal(queen, mv("a1", "e1")));
+        assert!(board.is_pseudo_legal(queen, mv("a1", "a8")));
+        // Along the long diagonal the friendly pawn on b2 blocks everything
+        assert!(!board.is_pseudo_legal(queen, mv("a1", "b2")));
+        assert!(!board.is_pseudo_legal(queen, mv("a1", "d4")));
+        assert!(!board.is_pseudo_legal(queen, mv("a1", "b3")));
+        assert_eq!(
+            game.clone().make_move(mv("a1", "h1")),
+            Err(MoveError::PathBlocked)
+        );
+
+        let game = Game::from_fen("4k3/8/8/8/3p4/8/8/Q3K3 w - - 0 1").unwrap();
+        let queen = game.board.square(sq("a1")).unwrap();
+        assert!(game.board.is_pseudo_legal(queen, mv("a1", "d4")));
+        assert!(!game.board.is_pseudo_legal(queen, mv("a1", "e5")));
+    }
+
+    #[test]
+    fn find_legal_moves_lists_one_pieces_moves() {
+        let game = Game::new();
+        let moves_of = |game: &Game, name: &str| {
+            let piece = game.board.square(sq(name)).unwrap();
+            game.find_legal_moves(piece)
+        };
+        assert_eq!(moves_of(&game, "g1"), vec![mv("g1", "f3"), mv("g1", "h3")]);
+        assert_eq!(moves_of(&game, "e2"), vec![mv("e2", "e3"), mv("e2", "e4")]);
+        assert!(moves_of(&game, "a1").is_empty());
+        // Black's pieces have no moves while White is to move
+        assert!(moves_of(&game, "g8").is_empty());
+
+        let queen = Game::from_fen("8/8/8/8/3Q4/8/K5k1/8 w - - 0 1").unwrap();
+        assert_eq!(moves_of(&queen, "d4").len(), 27);
+        // A pawn that has moved pushes one square, and takes what stands diagonally ahead
+        let pawn = Game::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(moves_of(&pawn, "e4"), vec![mv("e4", "d5"), mv("e4", "e5")]);
+    }
+
+    #[test]
+    fn board_apply_move_moves_and_captures() {
+        let mut board = Game::new().board;
+        assert!(board.apply_move(mv("e2", "e4")).is_none());
+        assert!(board.square(sq("e2")).is_none());
+        let pawn = board.square(sq("e4")).unwrap();
+        assert_eq!(
+            (pawn.piece_type, pawn.color),
+            (PieceType::Pawn, Color::White)
+        );
+        assert_eq!(pawn.position, sq("e4"));
+        assert!(!pawn.first_move);
+
+        board.apply_move(mv("d7", "d5"));
+        let victim = board.apply_move(mv("e4", "d5")).unwrap();
+        assert!(victim.captured && victim.color == Color::Black);
+        assert_eq!(board.iter_pieces().count(), 31);
+        // An empty start square changes nothing
+        assert!(board.apply_move(mv("e4", "e5")).is_none());
+        assert!(board.square(sq("e5")).is_none());
+        assert!(board.positions_in_sync());
+    }
+
+    #[test]
+    fn piece_positions_stay_in_sync_with_the_board() {
+        let mut game = Game::new();
+        assert!(game.board.positions_in_sync());
+        let black_king = game.board.square(sq("e8")).unwrap();
+        assert_eq!(black_king.position, sq("e8"));
+        for uci in [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f8c5", "d2d4", "c5d4",
+        ] {
+            game.make_uci_move(uci).unwrap();
+            assert!(game.board.positions_in_sync(), "{}", uci);
+        }
+        while game.unmake_move().is_some() {
+            assert!(game.board.positions_in_sync());
+        }
+        let mut game = Game::from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_uci_move("a7a8q").unwrap();
+        assert!(game.board.positions_in_sync());
+    }
+
+    #[test]
+    fn the_turn_passes_only_on_a_legal_typed_move() {
+        let mut game = Game::new();
+        for refused in ["e2e5\n", "Nf6", "hello", ""] {
+            assert!(game.play_typed_move(refused).is_err(), "{}", refused);
+            assert_eq!(game.turn, Color::White);
+        }
+        assert!(game.history.is_empty());
+        assert_eq!(game.play_typed_move("e4\n"), Ok(()));
+        assert_eq!(game.turn, Color::Black);
+        assert_eq!(game.history.len(), 1);
+        // White's pawn has already gone, and it is Black's move anyway
+        assert!(game.play_typed_move("e2e4").unwrap_err().contains("e4"));
+        assert_eq!(game.play_typed_move("e7e5"), Ok(()));
+        assert_eq!(game.turn, Color::White);
+        assert_eq!(game.history.len(), 2);
+    }
+
+    #[test]
+    fn a_blocked_rook_gives_no_check() {
+        let open = Game::from_fen("4k3/8/8/8/8/8/8/K3R3 b - - 0 1").unwrap();
+        assert!(open.is_in_check(Color::Black));
+        assert!(!open.is_in_check(Color::White));
+        // Blocked by a piece of either color
+        for fen in [
+            "4k3/4n3/8/8/8/8/8/K3R3 b - - 0 1",
+            "4k3/8/8/8/4N3/8/8/K3R3 b - - 0 1",
+        ] {
+            assert!(
+                !Game::from_fen(fen).unwrap().is_in_check(Color::Black),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn checkmate_and_stalemate_for_either_color() {
+        let mut game = Game::new();
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert!(game.is_checkmate(Color::White));
+        assert!(!game.is_checkmate(Color::Black));
+        assert!(!game.is_stalemate(Color::White));
+
+        // The black king on a8 has nowhere to go, whoever is to move
+        for fen in [
+            "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1",
+            "k7/2Q5/1K6/8/8/8/8/8 w - - 0 1",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            assert!(game.is_stalemate(Color::Black), "{}", fen);
+            assert!(!game.is_checkmate(Color::Black), "{}", fen);
+            assert!(!game.is_stalemate(Color::White), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn run_game_returns_how_the_game_ended() {
+        // Finished games return before asking for a move
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"],
+        );
+        assert_eq!(game.run_game(), GameResult::WhiteWins);
+
+        let mut game = Game::new();
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(game.run_game(), GameResult::BlackWins);
+
+        let mut game = Game::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.run_game(), GameResult::Draw(DrawReason::Stalemate));
+    }
+
+    #[test]
+    fn empty_squares_hold_no_piece() {
+        let game = Game::new();
+        for rank in 2..6 {
+            for file in 0..BOARD_DIMENSIONS as i8 {
+                assert!(game.board.square(Position { x: rank, y: file }).is_none());
+            }
+        }
+        assert_eq!(game.board.iter_pieces().count(), 32);
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        assert!(game.board.square(sq("e2")).is_none());
+    }
+
+    #[test]
+    fn opposite_colors() {
+        assert_eq!(Color::White.opposite(), Color::Black);
+        assert_eq!(Color::Black.opposite(), Color::White);
+        assert_eq!(Color::White.opposite().opposite(), Color::White);
+    }
+
+    #[test]
+    fn pawns_promote_to_a_queen_or_the_piece_named() {
+        let fen = "8/P6k/8/8/8/8/7p/K7 w - - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        let before = game.material_balance();
+        game.make_move(mv("a7", "a8")).unwrap();
+        let queen = game.board.square(sq("a8")).unwrap();
+        assert_eq!(
+            (queen.board_rep(), queen.piece_type),
+            ('Q', PieceType::Queen)
+        );
+        assert!(game.board.is_pseudo_legal(queen, mv("a8", "e4")));
+        assert!(game.material_balance() > before);
+        assert_eq!(game.material_balance(), game.count_material());
+
+        game.make_uci_move("h2h1n").unwrap();
+        let knight = game.board.square(sq("h1")).unwrap();
+        assert_eq!(knight.board_rep(), 'n');
+        assert!(game.board.is_pseudo_legal(knight, mv("h1", "g3")));
+        assert!(!game.board.is_pseudo_legal(knight, mv("h1", "h5")));
+        assert_eq!(game.to_fen(), "Q7/7k/8/8/8/8/8/K6n w - - 0 2");
+        game.unmake_move();
+        game.unmake_move();
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.material_balance(), before);
+
+        // Nothing promotes to a king, and only pawns reaching the last rank promote at all
+        let mut game = Game::from_fen(fen).unwrap();
+        let king = Move {
+            promotion: Some(PieceType::King),
+            ..mv("a7", "a8")
+        };
+        assert!(game.make_move(king).is_err());
+        let rook = Move {
+            promotion: Some(PieceType::Rook),
+            ..mv("a1", "b1")
+        };
+        assert!(game.make_move(rook).is_err());
+        game.make_move(game.parse_move("a8=N").unwrap()).unwrap();
+        assert_eq!(game.board.square(sq("a8")).unwrap().board_rep(), 'N');
+    }
+
+    #[test]
+    fn each_kind_has_its_letter_in_both_colors() {
+        use PieceType::*;
+        let pos = sq("a1");
+        for (kind, letter) in [
+            (Pawn, 'P'),
+            (Knight, 'N'),
+            (Bishop, 'B'),
+            (Rook, 'R'),
+            (Queen, 'Q'),
+            (King, 'K'),
+        ] {
+            let white = Piece::new(kind, Color::White, pos);
+            let black = Piece::new(kind, Color::Black, pos);
+            assert_eq!((white.kind(), white.color()), (kind, Color::White));
+            assert_eq!(white.board_rep(), letter);
+            assert_eq!(black.board_rep(), letter.to_ascii_lowercase());
+            let read = fen::piece_from_letter(black.board_rep(), pos).unwrap();
+            assert_eq!((read.kind(), read.color()), (kind, Color::Black));
+        }
+    }
+
+    #[test]
+    fn no_piece_moves_off_the_board() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/8").unwrap();
+        let at = |x: i8, y: i8| Position { x, y };
+        let cases = [
+            (
+                PieceType::Rook,
+                at(0, 0),
+                [at(-1, 0), at(0, -1), at(8, 0), at(0, 8)],
+            ),
+            (
+                PieceType::Queen,
+                at(7, 7),
+                [at(8, 7), at(7, 8), at(8, 8), at(-1, -1)],
+            ),
+            (
+                PieceType::King,
+                at(0, 0),
+                [at(-1, 0), at(0, -1), at(-1, -1), at(-1, 1)],
+            ),
+            (
+                PieceType::Knight,
+                at(0, 1),
+                [at(-1, -1), at(-1, 3), at(1, -1), at(2, -1)],
+            ),
+            (
+                PieceType::Bishop,
+                at(7, 7),
+                [at(8, 8), at(8, 6), at(6, 8), at(-1, -1)],
+            ),
+            (
+                PieceType::Pawn,
+                at(6, 7),
+                [at(7, 8), at(8, 7), at(8, 8), at(6, 8)],
+            ),
+        ];
+        for (kind, start, ends) in cases {
+            let piece = Piece::new(kind, Color::White, start);
+            for end in ends {
+                let mv = Move {
+                    start,
+                    end,
+                    promotion: None,
+                };
+                assert!(!board.is_pseudo_legal(piece, mv), "{:?} to {:?}", kind, end);
+            }
+        }
+        assert!(at(0, 0).is_on_board() && at(7, 7).is_on_board());
+        assert!(!at(-1, 3).is_on_board() && !at(3, 8).is_on_board());
+    }
+
+    #[test]
+    fn threefold_repetition_ends_the_game() {
+        let mut game = Game::new();
+        for round in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert!(game.result().is_none(), "round {} {}", round, uci);
+                game.make_uci_move(uci).unwrap();
+            }
+        }
+        assert!(game.is_threefold_repetition());
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(DrawReason::ThreefoldRepetition))
+        );
+        game.unmake_move();
+        assert!(!game.is_threefold_repetition());
+
+        // The first time the kings step away they give up castling, so the position before
+        // it never repeats and only the returns after it count
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "e7e5"]);
+        for _ in 0..2 {
+            play(&mut game, &["e1e2", "e8e7", "e2e1", "e7e8"]);
+        }
+        assert!(!game.is_threefold_repetition());
+        play(&mut game, &["e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert!(game.is_threefold_repetition());
+    }
+
+    #[test]
+    fn insufficient_material_covers_lone_minors_and_same_colored_bishops() {
+        let drawn = |fen: &str| Game::from_fen(fen).unwrap().is_insufficient_material();
+        assert!(drawn("8/8/4k3/8/8/2K5/8/8 w - - 0 1"));
+        assert!(drawn("8/8/4k3/8/8/2K5/8/5B2 w - - 0 1"));
+        assert!(drawn("8/8/4k3/8/8/2K5/8/5n2 w - - 0 1"));
+        // f1 and c8 are both light squares
+        assert!(drawn("2b5/8/4k3/8/8/2K5/8/5B2 w - - 0 1"));
+        // c1 is dark, so mate is possible
+        assert!(!drawn("2b5/8/4k3/8/8/2K5/8/2B5 w - - 0 1"));
+        assert!(!drawn("8/8/4k3/8/8/2K5/8/5R2 w - - 0 1"));
+        assert!(!drawn("8/8/4k3/8/8/2K5/8/4NN2 w - - 0 1"));
+        assert!(!drawn("8/8/4k3/8/8/2K5/4P3/8 w - - 0 1"));
+        assert!(!drawn("8/8/4k3/8/8/2K5/8/2B2B2 w - - 0 1"));
+
+        let game = Game::from_fen("2b5/8/4k3/8/8/2K5/8/5B2 w - - 0 1").unwrap();
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn board_display_puts_white_at_the_bottom() {
+        assert_eq!(
+            Game::new().board.to_string(),
+            "8 rnbqkbnr\n7 pppppppp\n6 ________\n5 ________\n4 ________\n3 ________\n\
+             2 PPPPPPPP\n1 RNBQKBNR\n  abcdefgh\n"
+        );
+        let board = Board::from_fen("8/8/8/8/4P3/8/8/8").unwrap();
+        assert!(board.to_string().contains("\n4 ____P___\n"));
+    }
+
+    #[test]
+    fn render_draws_letters_or_unicode_symbols() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/1N4n1").unwrap();
+        let unicode = board.render(RenderStyle::Unicode);
+        assert_eq!(unicode.lines().nth(7), Some("1 _♘____♞_"));
+        let ascii = board.render(RenderStyle::Ascii);
+        assert_eq!(ascii.lines().nth(7), Some("1 _N____n_"));
+        assert_eq!(ascii, board.to_string());
+        assert_eq!(unicode.lines().count(), ascii.lines().count());
+    }
+
+    #[test]
+    fn render_from_black_turns_the_board_around() {
+        assert_eq!(
+            Game::new().board.render_from(Color::Black),
+            "1 RNBKQBNR\n2 PPPPPPPP\n3 ________\n4 ________\n5 ________\n6 ________\n\
+             7 pppppppp\n8 rnbkqbnr\n  hgfedcba\n"
+        );
+
+        let board =
+            Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R").unwrap();
+        let white = board.render_from(Color::White);
+        let black = board.render_from(Color::Black);
+        assert_eq!(white, board.to_string());
+        // The squares without their labels read the same backwards
+        let squares =
+            |drawn: &str| -> String { drawn.lines().take(8).map(|line| &line[2..]).collect() };
+        assert_eq!(
+            squares(&white).chars().rev().collect::<String>(),
+            squares(&black)
+        );
+    }
+
+    #[test]
+    fn algebraic_names_round_trip_for_every_square() {
+        for pos in all_squares() {
+            let name = pos.to_algebraic();
+            assert_eq!(Position::from_algebraic(&name), Ok(pos), "{}", name);
+        }
+        // Ranks are x and files are y, counted from a1
+        assert_eq!(Position::from_algebraic("e2"), Ok(Position { x: 1, y: 4 }));
+        assert_eq!(Position { x: 7, y: 7 }.to_algebraic(), "h8");
+        for bad in ["i1", "a9", "a0", "", "e", "e22", "E2", "é2"] {
+            assert!(Position::from_algebraic(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn piece_at_reads_the_board_through_game_board() {
+        let game = Game::new();
+        let pawn = game.board().piece_at(sq("e2")).unwrap();
+        assert_eq!((pawn.kind(), pawn.color()), (PieceType::Pawn, Color::White));
+        let king = game.board().piece_at(sq("e8")).unwrap();
+        assert_eq!((king.kind(), king.color()), (PieceType::King, Color::Black));
+        assert!(game.board().piece_at(sq("e4")).is_none());
+        assert!(game.board().piece_at(Position { x: -1, y: 0 }).is_none());
+        assert!(game.board().piece_at(Position { x: 0, y: 8 }).is_none());
+    }
+
+    #[test]
+    fn all_legal_moves_for_either_color() {
+        let game = Game::new();
+        assert_eq!(game.all_legal_moves(Color::White), game.legal_moves());
+        assert_eq!(game.all_legal_moves(Color::Black).len(), 20);
+
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        assert_eq!(game.all_legal_moves(Color::Black).len(), 20);
+        // The bishop and queen have room now as well
+        assert_eq!(game.all_legal_moves(Color::White).len(), 30);
+        assert_eq!(game.turn, Color::Black);
+    }
+
+    #[test]
+    fn equal_moves_hash_as_one() {
+        use std::collections::HashSet;
+        let a = mv("e2", "e4");
+        let b = Move {
+            start: Position { x: 1, y: 4 },
+            end: Position { x: 3, y: 4 },
+            promotion: None,
+        };
+        assert_eq!(a, b);
+        let set: HashSet<Move> = vec![a, b].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        let queen = Move {
+            promotion: Some(PieceType::Queen),
+            ..a
+        };
+        assert_ne!(queen, a);
+        let starts: HashSet<Position> = Game::new().legal_moves().iter().map(|m| m.start).collect();
+        // Eight pawns and two knights
+        assert_eq!(starts.len(), 10);
+    }
+
+    #[test]
+    fn a_cloned_game_plays_on_its_own() {
+        let game = Game::new();
+        let mut trial = game.clone();
+        trial.make_uci_move("e2e4").unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert!(game.board().piece_at(sq("e4")).is_none());
+        assert!(trial.board().piece_at(sq("e4")).is_some());
+        assert!(game.history.is_empty());
+        assert_eq!(trial.history.len(), 1);
+        assert_eq!(game.turn, Color::White);
+    }
+
+    #[test]
+    fn board_dispatches_each_piece_to_its_own_rule() {
+        for placement in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N",
+        ] {
+            let board = Board::from_fen(placement).unwrap();
+            for (start, piece) in board.iter_pieces() {
+                let rule: fn(Piece, Move, &Board) -> bool = match piece.kind() {
+                    PieceType::Pawn => is_pawn_move_valid,
+                    PieceType::Knight => is_knight_move_valid,
+                    PieceType::Bishop => is_bishop_move_valid,
+                    PieceType::Rook => is_rook_move_valid,
+                    PieceType::Queen => is_queen_move_valid,
+                    PieceType::King => is_king_move_valid,
+                };
+                for end in all_squares() {
+                    let mv = Move {
+                        start,
+                        end,
+                        promotion: None,
+                    };
+                    let own_piece = board.color_at(end) == Some(piece.color);
+                    assert_eq!(
+                        board.is_pseudo_legal(piece, mv),
+                        !own_piece && rule(piece, mv, &board),
+                        "{} in {}",
+                        mv.to_uci(),
+                        placement
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pieces_lists_one_sides_pieces() {
+        let game = Game::new();
+        let board = game.board();
+        for &color in &[Color::White, Color::Black] {
+            let pieces: Vec<_> = board.pieces(color).collect();
+            assert_eq!(pieces.len(), 16);
+            assert!(pieces
+                .iter()
+                .all(|(pos, piece)| piece.color() == color && piece.position == *pos));
+        }
+        // Rank by rank from a1
+        let first = board.pieces(Color::Black).next().unwrap();
+        assert_eq!(first.0, sq("a7"));
+
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "d7d5", "e4d5"]);
+        assert_eq!(game.board().pieces(Color::White).count(), 16);
+        assert_eq!(game.board().pieces(Color::Black).count(), 15);
+    }
+
+    #[test]
+    fn pawns_attack_diagonally_but_not_ahead() {
+        let board = Game::new().board().clone();
+        assert!(board.is_square_attacked(sq("d3"), Color::White));
+        assert!(board.is_square_attacked(sq("f3"), Color::White));
+        // The square a pawn pushes to is not one it attacks
+        assert!(!board.is_square_attacked(sq("e4"), Color::White));
+        assert!(board.is_square_attacked(sq("f6"), Color::Black));
+        assert!(!board.is_square_attacked(sq("e5"), Color::Black));
+        // A side never attacks a square holding its own piece
+        assert!(!board.is_square_attacked(sq("e2"), Color::White));
+
+        let board = Board::from_fen("4k3/8/8/8/4p3/8/8/R3K3").unwrap();
+        assert!(board.is_square_attacked(sq("d3"), Color::Black));
+        assert!(!board.is_square_attacked(sq("e3"), Color::Black));
+        assert!(board.is_square_attacked(sq("a8"), Color::White));
+        assert!(!board.is_square_attacked(sq("b2"), Color::White));
+    }
+
+    #[test]
+    fn no_piece_lands_on_its_own_side() {
+        let board = Board::from_fen("4k3/8/p7/8/8/P7/8/R3K3").unwrap();
+        let rook = *board.piece_at(sq("a1")).unwrap();
+        assert!(board.is_pseudo_legal(rook, mv("a1", "a2")));
+        assert!(!board.is_pseudo_legal(rook, mv("a1", "a3")));
+        assert!(!board.is_pseudo_legal(rook, mv("a1", "e1")));
+
+        let game = Game::from_fen("4k3/8/p7/8/8/P7/8/R3K3 w - - 0 1").unwrap();
+        assert!(!game.legal_moves().contains(&mv("a1", "a3")));
+        let game = Game::from_fen("4k3/8/8/8/8/p7/8/R3K3 w - - 0 1").unwrap();
+        assert!(game.legal_moves().contains(&mv("a1", "a3")));
+    }
+
+    #[test]
+    fn the_fullmove_number_counts_after_each_black_move() {
+        let mut game = Game::new();
+        assert_eq!(game.fullmove_number(), 1);
+        play(&mut game, &["e2e4"]);
+        assert_eq!(game.fullmove_number(), 1);
+        play(&mut game, &["e7e5", "g1f3"]);
+        assert_eq!(game.fullmove_number(), 2);
+        assert!(game.to_fen().ends_with(" 1 2"));
+        play(&mut game, &["b8c6"]);
+        assert_eq!(game.fullmove_number(), 3);
+        game.undo().unwrap();
+        assert_eq!(game.fullmove_number(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trips_a_game_in_progress() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "a7a6", "e4e5", "a8a7", "e1e2", "d7d5"]);
+        let json = serde_json::to_string(&game).unwrap();
+        let mut restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.turn(), Color::White);
+        assert_eq!(restored.castling_rights(), game.castling_rights());
+        assert!(restored.castling_rights().black_king && !restored.castling_rights().white_king);
+        assert_eq!(restored.en_passant(), Some(sq("d6")));
+        assert_eq!(restored.legal_moves(), game.legal_moves());
+        assert_eq!(restored.to_fen(), game.to_fen());
+        // The restored game plays on, en passant included
+        restored.make_uci_move("e5d6").unwrap();
+        assert!(restored.board().piece_at(sq("d5")).is_none());
+        restored.undo().unwrap();
+        assert_eq!(restored.zobrist_hash(), game.zobrist_hash());
     }
 }
@@ -0,0 +1,240 @@
+use std::error::Error;
+use std::fmt;
+
+use super::fen::piece_from_letter;
+use super::{CastlingRights, Color, Game, Position, BOARD_DIMENSIONS};
+
+/// Layout written by to_bytes, stored in the first byte so old data can still be recognized
+/// if the layout ever changes
+const FORMAT_VERSION: u8 = 1;
+
+/// Version byte, two squares per byte for the 64 squares, the flags byte and the en passant byte
+const ENCODED_LENGTH: usize = 1 + BOARD_DIMENSIONS * BOARD_DIMENSIONS / 2 + 2;
+
+/// Piece letters in the order of their codes, which start at 1 since 0 is an empty square
+const PIECE_LETTERS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+/// Added to a piece code for Black's pieces
+const BLACK_BIT: u8 = 8;
+
+/// Set in the flags byte when Black is to move
+const BLACK_TO_MOVE: u8 = 1;
+
+/// Flags for White kingside, White queenside, Black kingside and Black queenside castling
+const CASTLING_FLAGS: [u8; 4] = [2, 4, 8, 16];
+
+/// Written in place of a square when there is no en passant square
+const NO_EN_PASSANT: u8 = 0xFF;
+
+/// Reasons bytes cannot be read back as a position
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    /// Holds the number of bytes given
+    WrongLength(usize),
+    UnsupportedVersion(u8),
+    /// A square held a code that is not a piece
+    InvalidPiece(u8),
+    InvalidEnPassant(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::WrongLength(length) => {
+                write!(f, "expected {} bytes, found {}", ENCODED_LENGTH, length)
+            }
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            DecodeError::InvalidPiece(code) => write!(f, "invalid piece code {}", code),
+            DecodeError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square {}", square)
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl Game {
+    /// Packs the position into 35 bytes: a version byte, four bits per square from a1 to h8,
+    /// a flags byte and the en passant square. Bit 0 of the flags is set when Black is to move
+    /// and bits 1 to 4 hold the castling rights. Move history and clocks are not stored
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        let codes: Vec<u8> = self
+            .board
+            .squares()
+            .iter()
+            .map(|square| match square {
+                Some(piece) => {
+                    let letter = piece.piece_type.letter();
+                    let index = PIECE_LETTERS.iter().position(|&l| l == letter).unwrap_or(0);
+                    let color = match piece.color {
+                        Color::Black => BLACK_BIT,
+                        _ => 0,
+                    };
+                    index as u8 + 1 + color
+                }
+                None => 0,
+            })
+            .collect();
+        // The lower square of each pair goes in the low four bits
+        bytes.extend(codes.chunks(2).map(|pair| pair[0] | pair[1] << 4));
+        let rights = [
+            self.castling.white_king,
+            self.castling.white_queen,
+            self.castling.black_king,
+            self.castling.black_queen,
+        ];
+        let castling = rights
+            .iter()
+            .zip(CASTLING_FLAGS.iter())
+            .filter(|(&held, _)| held)
+            .fold(0, |flags, (_, flag)| flags | flag);
+        bytes.push(
+            castling
+                | match self.turn {
+                    Color::Black => BLACK_TO_MOVE,
+                    _ => 0,
+                },
+        );
+        bytes.push(match self.en_passant {
+            Some(ep) => ep.x as u8 * BOARD_DIMENSIONS as u8 + ep.y as u8,
+            None => NO_EN_PASSANT,
+        });
+        bytes
+    }
+
+    /// Reads a position written by to_bytes. The game starts from it with no move history
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, DecodeError> {
+        if bytes.len() != ENCODED_LENGTH {
+            return Err(DecodeError::WrongLength(bytes.len()));
+        }
+        if bytes[0] != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(bytes[0]));
+        }
+        let mut game = Game::empty();
+        let board = &bytes[1..ENCODED_LENGTH - 2];
+        for (index, &pair) in board.iter().enumerate() {
+            for (half, code) in [pair & 0xF, pair >> 4].iter().enumerate() {
+                if *code == 0 {
+                    continue;
+                }
+                let letter = ((code & !BLACK_BIT) as usize)
+                    .checked_sub(1)
+                    .and_then(|index| PIECE_LETTERS.get(index))
+                    .ok_or(DecodeError::InvalidPiece(*code))?;
+                let letter = if code & BLACK_BIT != 0 {
+                    letter.to_ascii_lowercase()
+                } else {
+                    *letter
+                };
+                let square = index * 2 + half;
+                let pos = Position {
+                    x: (square / BOARD_DIMENSIONS) as i8,
+                    y: (square % BOARD_DIMENSIONS) as i8,
+                };
+                if let Some(piece) = piece_from_letter(letter, pos) {
+                    game.board.place(piece);
+                }
+            }
+        }
+
+        let flags = bytes[ENCODED_LENGTH - 2];
+        if flags & BLACK_TO_MOVE != 0 {
+            game.turn = Color::Black;
+        }
+        game.castling = CastlingRights {
+            white_king: flags & CASTLING_FLAGS[0] != 0,
+            white_queen: flags & CASTLING_FLAGS[1] != 0,
+            black_king: flags & CASTLING_FLAGS[2] != 0,
+            black_queen: flags & CASTLING_FLAGS[3] != 0,
+            ..CastlingRights::default()
+        };
+        game.castling.locate_rooks(&game.board);
+        let en_passant = bytes[ENCODED_LENGTH - 1];
+        if en_passant != NO_EN_PASSANT {
+            let pos = Position {
+                x: (en_passant / BOARD_DIMENSIONS as u8) as i8,
+                y: (en_passant % BOARD_DIMENSIONS as u8) as i8,
+            };
+            if !pos.is_on_board() {
+                return Err(DecodeError::InvalidEnPassant(en_passant));
+            }
+            game.en_passant = Some(pos);
+        }
+        game.material = game.count_material();
+        game.start_hashing();
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShiftRng;
+
+    /// The FEN without the clocks, which to_bytes leaves out
+    fn position_fields(game: &Game) -> String {
+        let fen = game.to_fen();
+        fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn bytes_round_trip_the_position() {
+        let mut played = Game::new();
+        for uci in ["e2e4", "c7c5", "e4e5", "d7d5"] {
+            played.make_uci_move(uci).unwrap();
+        }
+        let mut games = vec![
+            Game::new(),
+            played,
+            Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1").unwrap(),
+            Game::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9")
+                .unwrap(),
+        ];
+        let mut rng = XorShiftRng::new(251);
+        games.extend((0..20).map(|_| Game::random_position(&mut rng)));
+        for game in games {
+            let bytes = game.to_bytes();
+            assert_eq!(bytes.len(), ENCODED_LENGTH);
+            let back = Game::from_bytes(&bytes).unwrap();
+            assert_eq!(position_fields(&back), position_fields(&game));
+            assert_eq!(back.castling, game.castling);
+            assert_eq!(back.zobrist_hash(), game.zobrist_hash());
+            assert_eq!(back.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn from_bytes_rejects_damaged_data() {
+        assert_eq!(
+            Game::from_bytes(&[FORMAT_VERSION, 0]).err(),
+            Some(DecodeError::WrongLength(2))
+        );
+        let mut bytes = Game::new().to_bytes();
+        bytes[0] = 9;
+        assert_eq!(
+            Game::from_bytes(&bytes).err(),
+            Some(DecodeError::UnsupportedVersion(9))
+        );
+        let mut bytes = Game::new().to_bytes();
+        bytes[10] = 0x07;
+        assert_eq!(
+            Game::from_bytes(&bytes).err(),
+            Some(DecodeError::InvalidPiece(7))
+        );
+        bytes[10] = 0x80;
+        assert_eq!(
+            Game::from_bytes(&bytes).err(),
+            Some(DecodeError::InvalidPiece(8))
+        );
+        let mut bytes = Game::new().to_bytes();
+        bytes[ENCODED_LENGTH - 1] = 64;
+        assert_eq!(
+            Game::from_bytes(&bytes).err(),
+            Some(DecodeError::InvalidEnPassant(64))
+        );
+    }
+}
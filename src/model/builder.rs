@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use super::{FenError, Game, RenderStyle, TimeControl};
+
+/// Deepest search the difficulty setting allows
+const MAX_DIFFICULTY: u32 = 10;
+
+/// Reasons a GameBuilder cannot produce a game
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BuildError {
+    /// The starting position was not a valid FEN record
+    Fen(FenError),
+    /// Difficulty must be between 1 and 10
+    Difficulty(u32),
+    /// A clock must give each player some time to start with
+    Clock,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Fen(err) => write!(f, "invalid starting position: {}", err),
+            BuildError::Difficulty(level) => {
+                write!(f, "difficulty {} is outside 1 to {}", level, MAX_DIFFICULTY)
+            }
+            BuildError::Clock => write!(f, "the clock has no starting time"),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Fen(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Configures a game before it starts. Anything left unset keeps the value Game::new uses
+#[derive(Clone, Default)]
+pub struct GameBuilder {
+    fen: Option<String>,
+    difficulty: Option<u32>,
+    charset: RenderStyle,
+    flip: bool,
+    clock: Option<TimeControl>,
+}
+
+impl GameBuilder {
+    pub fn new() -> GameBuilder {
+        GameBuilder::default()
+    }
+
+    /// Start from the position described by a FEN record instead of the standard one
+    pub fn fen(mut self, fen: &str) -> GameBuilder {
+        self.fen = Some(fen.to_string());
+        self
+    }
+
+    /// How many plies deep the engine searches, from 1 to 10
+    pub fn difficulty(mut self, difficulty: u32) -> GameBuilder {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Characters to draw the board with
+    pub fn charset(mut self, charset: RenderStyle) -> GameBuilder {
+        self.charset = charset;
+        self
+    }
+
+    /// Print the board from the other side
+    pub fn flip(mut self, flip: bool) -> GameBuilder {
+        self.flip = flip;
+        self
+    }
+
+    pub fn clock(mut self, clock: TimeControl) -> GameBuilder {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Creates the configured game, checking every setting
+    pub fn build(self) -> Result<Game, BuildError> {
+        let mut game = match &self.fen {
+            Some(fen) => Game::from_fen(fen).map_err(BuildError::Fen)?,
+            None => Game::new(),
+        };
+        if let Some(difficulty) = self.difficulty {
+            if difficulty == 0 || difficulty > MAX_DIFFICULTY {
+                return Err(BuildError::Difficulty(difficulty));
+            }
+            game.difficulty = difficulty;
+        }
+        if let Some(clock) = self.clock {
+            if clock.initial == Duration::from_secs(0) {
+                return Err(BuildError::Clock);
+            }
+        }
+        game.charset = self.charset;
+        game.flipped = self.flip;
+        game.clock = self.clock;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_configured_game() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 7";
+        let clock = TimeControl {
+            initial: Duration::from_secs(300),
+            increment: Duration::from_secs(2),
+        };
+        let game = GameBuilder::new()
+            .fen(fen)
+            .charset(RenderStyle::Unicode)
+            .difficulty(4)
+            .flip(true)
+            .clock(clock)
+            .build()
+            .unwrap();
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.charset(), RenderStyle::Unicode);
+        assert_eq!(game.difficulty(), 4);
+        assert!(game.is_flipped());
+        assert_eq!(game.clock(), Some(clock));
+
+        let plain = GameBuilder::new().build().unwrap();
+        assert_eq!(plain.to_fen(), Game::new().to_fen());
+        assert_eq!(plain.clock(), None);
+    }
+
+    #[test]
+    fn refuses_bad_settings() {
+        assert!(matches!(
+            GameBuilder::new().fen("not a position").build(),
+            Err(BuildError::Fen(_))
+        ));
+        assert!(matches!(
+            GameBuilder::new().difficulty(11).build(),
+            Err(BuildError::Difficulty(11))
+        ));
+        assert!(matches!(
+            GameBuilder::new().difficulty(0).build(),
+            Err(BuildError::Difficulty(0))
+        ));
+        let no_time = TimeControl {
+            initial: Duration::from_secs(0),
+            increment: Duration::from_secs(5),
+        };
+        assert!(matches!(
+            GameBuilder::new().clock(no_time).build(),
+            Err(BuildError::Clock)
+        ));
+    }
+}
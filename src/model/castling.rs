@@ -0,0 +1,486 @@
+use super::{
+    promotion_rank, squares_between, Board, Color, Game, Move, Piece, PieceType, Position,
+    BOARD_DIMENSIONS,
+};
+
+/// File the king starts on in standard chess, from where a castle is written as a two-square
+/// king move
+const KING_FILE: i8 = 4;
+
+/// Files the king and rook land on when castling toward the h-file, whatever files they
+/// started on
+const KINGSIDE_KING_FILE: i8 = 6;
+const KINGSIDE_ROOK_FILE: i8 = 5;
+
+/// Files the king and rook land on when castling toward the a-file
+const QUEENSIDE_KING_FILE: i8 = 2;
+const QUEENSIDE_ROOK_FILE: i8 = 3;
+
+/// Which castles each side may still make, and with which rooks. A right is lost for good once
+/// the king or the rook on that side moves, or the rook is captured
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    pub white_king: bool,
+    pub white_queen: bool,
+    pub black_king: bool,
+    pub black_queen: bool,
+    /// Files of the rooks castled with, for White kingside, White queenside, Black kingside
+    /// and Black queenside in that order. The corners in standard chess, and wherever the
+    /// rooks started in Chess960
+    pub(super) rook_files: [i8; 4],
+}
+
+impl Default for CastlingRights {
+    /// No castles left, with the rooks in the corners should a right be granted later
+    fn default() -> CastlingRights {
+        let corner = BOARD_DIMENSIONS as i8 - 1;
+        CastlingRights {
+            white_king: false,
+            white_queen: false,
+            black_king: false,
+            black_queen: false,
+            rook_files: [corner, 0, corner, 0],
+        }
+    }
+}
+
+impl CastlingRights {
+    /// Every castle still available, as at the start of a game
+    pub fn all() -> CastlingRights {
+        CastlingRights {
+            white_king: true,
+            white_queen: true,
+            black_king: true,
+            black_queen: true,
+            ..CastlingRights::default()
+        }
+    }
+
+    /// Whether `color` may still castle on the king's side, or on the queen's side when
+    /// `kingside` is false
+    pub fn allows(&self, color: Color, kingside: bool) -> bool {
+        match (color, kingside) {
+            (Color::White, true) => self.white_king,
+            (Color::White, false) => self.white_queen,
+            (Color::Black, true) => self.black_king,
+            (Color::Black, false) => self.black_queen,
+        }
+    }
+
+    /// File of the rook `color` castles with on the king's side, or on the queen's side when
+    /// `kingside` is false
+    pub fn rook_file(&self, color: Color, kingside: bool) -> i8 {
+        let index = match color {
+            Color::White => 0,
+            Color::Black => 2,
+        };
+        self.rook_files[index + if kingside { 0 } else { 1 }]
+    }
+
+    /// Points every right at the outermost rook of its color on the back rank on that side of
+    /// the king, which is how KQkq is read in FEN for Chess960 starts. A right whose rook
+    /// cannot be found keeps the file it had
+    pub(super) fn locate_rooks(&mut self, board: &Board) {
+        for (index, &color) in [Color::White, Color::Black].iter().enumerate() {
+            let rank = promotion_rank(color.opposite());
+            let king = match board.find_king(color) {
+                Some(king) if king.x == rank => king.y,
+                _ => continue,
+            };
+            let corner = BOARD_DIMENSIONS as i8 - 1;
+            let kingside: Vec<i8> = (king + 1..=corner).rev().collect();
+            for (side, files) in [kingside, (0..king).collect()].iter().enumerate() {
+                let rook = files.iter().copied().find(|&file| {
+                    board
+                        .square(Position { x: rank, y: file })
+                        .is_some_and(|piece| {
+                            piece.piece_type == PieceType::Rook && piece.color == color
+                        })
+                });
+                if let Some(file) = rook {
+                    self.rook_files[index * 2 + side] = file;
+                }
+            }
+        }
+    }
+
+    /// Whether the pieces for a right are where castling needs them: the king of `color` on its
+    /// back rank and one of its rooks on the file the right castles with, on the matching side
+    /// of the king
+    pub(super) fn has_pieces_for(&self, board: &Board, color: Color, kingside: bool) -> bool {
+        let rank = promotion_rank(color.opposite());
+        let king = match board.find_king(color) {
+            Some(king) if king.x == rank => king,
+            _ => return false,
+        };
+        let file = self.rook_file(color, kingside);
+        let rook = board
+            .piece_at(Position { x: rank, y: file })
+            .is_some_and(|piece| piece.piece_type == PieceType::Rook && piece.color == color);
+        rook && (file > king.y) == kingside
+    }
+
+    /// Gives up every right whose king or rook has left its square, as after editing the board
+    /// by hand, then points the rights that are left at the rooks now on the board
+    pub(super) fn drop_missing_pieces(&mut self, board: &Board) {
+        for &color in &[Color::White, Color::Black] {
+            for &kingside in &[true, false] {
+                if self.has_pieces_for(board, color, kingside) {
+                    continue;
+                }
+                match (color, kingside) {
+                    (Color::White, true) => self.white_king = false,
+                    (Color::White, false) => self.white_queen = false,
+                    (Color::Black, true) => self.black_king = false,
+                    (Color::Black, false) => self.black_queen = false,
+                }
+            }
+        }
+        self.locate_rooks(board);
+    }
+
+    /// Gives up the rights lost by `piece` leaving `from`, whether it moved away or was captured
+    /// there: both of them for a king, and the one on its own side for a castling rook leaving
+    /// its square
+    pub(super) fn remove_for_move(&mut self, piece: Piece, from: Position) {
+        let king_file = self.rook_file(piece.color, true);
+        let queen_file = self.rook_file(piece.color, false);
+        let (king, queen) = match piece.color {
+            Color::White => (&mut self.white_king, &mut self.white_queen),
+            Color::Black => (&mut self.black_king, &mut self.black_queen),
+        };
+        match piece.piece_type {
+            PieceType::King => {
+                *king = false;
+                *queen = false;
+            }
+            PieceType::Rook if from.x == promotion_rank(piece.color.opposite()) => {
+                if from.y == king_file {
+                    *king = false;
+                } else if from.y == queen_file {
+                    *queen = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Which way `mv` castles `king`: `Some(true)` toward the h-file, `Some(false)` toward the
+    /// a-file and `None` if it is not a castle these rights allow. A king starting on the
+    /// e-file castles by moving two squares, as in standard chess. Any other king castles by
+    /// moving onto the rook it castles with, since in Chess960 it may only move one square or
+    /// none at all
+    fn castle_side(&self, king: Piece, mv: Move) -> Option<bool> {
+        let rank = promotion_rank(king.color.opposite());
+        if king.piece_type != PieceType::King || mv.start.x != rank || mv.end.x != rank {
+            return None;
+        }
+        [true, false].iter().copied().find(|&kingside| {
+            let target = if mv.start.y != KING_FILE {
+                self.rook_file(king.color, kingside)
+            } else if kingside {
+                KINGSIDE_KING_FILE
+            } else {
+                QUEENSIDE_KING_FILE
+            };
+            self.allows(king.color, kingside) && mv.end.y == target
+        })
+    }
+}
+
+/// The rook's half of a castle: from the file it castles from to the f-file, or to the d-file
+/// on the queen's side. `None` unless `mv` is a castle that `rights` allow `king` to make
+pub(super) fn rook_move(rights: &CastlingRights, king: Piece, mv: Move) -> Option<Move> {
+    let kingside = rights.castle_side(king, mv)?;
+    Some(Move {
+        start: Position {
+            x: mv.start.x,
+            y: rights.rook_file(king.color, kingside),
+        },
+        end: Position {
+            x: mv.start.x,
+            y: if kingside {
+                KINGSIDE_ROOK_FILE
+            } else {
+                QUEENSIDE_ROOK_FILE
+            },
+        },
+        promotion: None,
+    })
+}
+
+/// Where the king of a castle lands: just past where `rook_move` puts the rook, on the g-file
+/// or the c-file
+pub(super) fn king_landing(rook_move: Move) -> Position {
+    Position {
+        x: rook_move.end.x,
+        y: if rook_move.end.y == KINGSIDE_ROOK_FILE {
+            KINGSIDE_KING_FILE
+        } else {
+            QUEENSIDE_KING_FILE
+        },
+    }
+}
+
+impl Game {
+    /// Which castles each side may still make
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// Which way `mv` castles: `Some(true)` toward the h-file, `Some(false)` toward the a-file
+    /// and `None` if it is not a castle the side on its start square still has the right to
+    pub(super) fn castle_side(&self, mv: Move) -> Option<bool> {
+        let king = self.board.square(mv.start)?;
+        self.castling.castle_side(king, mv)
+    }
+
+    /// Whether the castle `mv` has room: its rook must be in place, and every square the king
+    /// or rook crosses or lands on must be empty but for the king and rook themselves
+    pub(super) fn is_castle_path_clear(&self, mv: Move) -> bool {
+        let king = match self.board.square(mv.start) {
+            Some(king) => king,
+            None => return false,
+        };
+        let rook_move = match rook_move(&self.castling, king, mv) {
+            Some(rook_move) => rook_move,
+            None => return false,
+        };
+        let has_rook = self
+            .board
+            .square(rook_move.start)
+            .is_some_and(|rook| rook.piece_type == PieceType::Rook && rook.color == king.color);
+        let files = [
+            mv.start.y,
+            king_landing(rook_move).y,
+            rook_move.start.y,
+            rook_move.end.y,
+        ];
+        let low = files.iter().copied().min().unwrap_or(0);
+        let high = files.iter().copied().max().unwrap_or(0);
+        has_rook
+            && (low..=high)
+                .map(|y| Position { x: mv.start.x, y })
+                .filter(|&pos| pos != mv.start && pos != rook_move.start)
+                .all(|pos| self.board.is_empty(pos))
+    }
+
+    /// Whether the castle `mv` may be played apart from leaving the king in check: the side
+    /// must still have the right and room to castle, must not be in check, and the king must
+    /// not pass over or land on an attacked square
+    pub(super) fn can_castle(&self, mv: Move) -> bool {
+        let king = match self.board.square(mv.start) {
+            Some(king) => king,
+            None => return false,
+        };
+        let landing = match rook_move(&self.castling, king, mv) {
+            Some(rook_move) => king_landing(rook_move),
+            None => return false,
+        };
+        if !self.is_castle_path_clear(mv) || self.is_in_check(king.color) {
+            return false;
+        }
+        let enemy = king.color.opposite();
+        squares_between(mv.start, landing)
+            .into_iter()
+            .chain(Some(landing))
+            .all(|pos| !self.board.is_square_attacked(pos, enemy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_coordinate_move, Game, Move, PieceType};
+    use super::*;
+
+    fn castle(game: &mut Game, uci: &str, king: &str, rook: &str) {
+        let before = game.to_fen();
+        let mv = parse_coordinate_move(uci).unwrap();
+        assert!(game.legal_moves().contains(&mv), "{} in {}", uci, before);
+        game.make_move(mv).unwrap();
+        for (square, piece_type) in [(king, PieceType::King), (rook, PieceType::Rook)] {
+            let pos = Position::from_algebraic(square).unwrap();
+            assert_eq!(
+                game.board().piece_type_at(pos),
+                Some(piece_type),
+                "{}",
+                square
+            );
+        }
+        game.undo();
+        assert_eq!(game.to_fen(), before);
+    }
+
+    #[test]
+    fn back_rank_rights_follow_the_rooks() {
+        use PieceType::*;
+        let game = Game::from_back_rank([Bishop, Rook, Knight, King, Queen, Rook, Knight, Bishop])
+            .unwrap();
+        let rights = game.castling_rights();
+        assert_eq!(rights, {
+            let mut all = CastlingRights::all();
+            all.rook_files = [5, 1, 5, 1];
+            all
+        });
+        assert_eq!(
+            game.to_fen(),
+            "brnkqrnb/pppppppp/8/8/8/8/PPPPPPPP/BRNKQRNB w KQkq - 0 1"
+        );
+
+        let game = Game::from_back_rank([Queen, Knight, Bishop, Rook, King, Bishop, Knight, Rook])
+            .unwrap();
+        let rights = game.castling_rights();
+        assert!(rights.white_king && rights.white_queen);
+        assert_eq!(rights.rook_file(Color::Black, false), 3);
+    }
+
+    #[test]
+    fn chess960_castles_land_on_the_standard_squares() {
+        let mut game =
+            Game::from_fen("1r1k1r2/pppppppp/8/8/8/8/PPPPPPPP/1R1K1R2 w KQkq - 0 1").unwrap();
+        castle(&mut game, "d1f1", "g1", "f1");
+        castle(&mut game, "d1b1", "c1", "d1");
+        assert_eq!(
+            game.move_to_san(parse_coordinate_move("d1f1").unwrap()),
+            "O-O"
+        );
+        assert_eq!(
+            game.parse_move("O-O-O").unwrap().end,
+            Position { x: 0, y: 1 }
+        );
+
+        // A king already on the g-file castles without moving
+        let mut game = Game::from_fen("6kr/8/8/8/8/8/8/6KR w Kk - 0 1").unwrap();
+        castle(&mut game, "g1h1", "g1", "f1");
+        // Standard castles are still written as the king moving two squares
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        castle(&mut game, "e1g1", "g1", "f1");
+        castle(&mut game, "e1c1", "c1", "d1");
+        assert!(!game.is_castle(parse_coordinate_move("e1h1").unwrap()));
+    }
+
+    #[test]
+    fn chess960_castles_need_room_and_safety() {
+        // The knight on g1 stands where the king would land
+        let game =
+            Game::from_fen("1r1k1r2/pppppppp/8/8/8/8/PPPPPPPP/1R1K1RN1 w KQkq - 0 1").unwrap();
+        let mv = parse_coordinate_move("d1f1").unwrap();
+        assert!(!game.legal_moves().contains(&mv));
+        // The rook on f8 covers f1, which the king passes over
+        let game = Game::from_fen("1r1k1r2/8/8/8/8/8/8/1R1K1R2 w KQkq - 0 1").unwrap();
+        let mv = parse_coordinate_move("d1f1").unwrap();
+        assert!(!game.legal_moves().contains(&mv));
+    }
+
+    #[test]
+    fn moving_a_castling_rook_gives_up_its_side() {
+        let mut game = Game::from_fen("1r1k1r2/8/8/8/8/8/8/1R1K1R2 w KQkq - 0 1").unwrap();
+        game.make_uci_move("f1f2").unwrap();
+        let rights = game.castling_rights();
+        assert!(!rights.white_king && rights.white_queen && rights.black_king);
+        let mv = Move {
+            start: Position { x: 0, y: 3 },
+            end: Position { x: 0, y: 5 },
+            promotion: None,
+        };
+        assert!(!game.is_castle(mv));
+    }
+
+    #[test]
+    fn chess960_perft_matches_published_counts() {
+        // KQkq names the outermost rooks, here the h- and f-files
+        let game =
+            Game::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9")
+                .unwrap();
+        assert_eq!(game.castling_rights().rook_file(Color::White, false), 5);
+        assert_eq!(
+            (game.perft(1), game.perft(2), game.perft(3)),
+            (21, 528, 12189)
+        );
+    }
+
+    #[test]
+    fn both_sides_castle_both_ways() {
+        let mut game =
+            Game::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+        castle(&mut game, "e1g1", "g1", "f1");
+        castle(&mut game, "e1c1", "c1", "d1");
+        game.make_uci_move("e1g1").unwrap();
+        assert!(!game.castling_rights().white_king && !game.castling_rights().white_queen);
+        castle(&mut game, "e8g8", "g8", "f8");
+        castle(&mut game, "e8c8", "c8", "d8");
+        assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+    }
+
+    #[test]
+    fn castling_needs_empty_safe_squares_and_unmoved_pieces() {
+        let castles = |fen: &str| {
+            let game = Game::from_fen(fen).unwrap();
+            ["e1g1", "e1c1"].map(|uci| {
+                let mv = parse_coordinate_move(uci).unwrap();
+                game.legal_moves().contains(&mv)
+            })
+        };
+        // A knight in the way on each side, then on b1 where only the rook passes
+        assert_eq!(
+            castles("r3k2r/8/8/8/8/8/8/R2NK1NR w KQkq - 0 1"),
+            [false, false]
+        );
+        assert_eq!(
+            castles("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1"),
+            [true, false]
+        );
+        // Out of, through and into check
+        assert_eq!(
+            castles("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"),
+            [true, true]
+        );
+        assert_eq!(castles("4r3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), [false, false]);
+        assert_eq!(castles("5r2/8/8/8/8/8/8/R3K2R w KQ - 0 1"), [false, true]);
+        assert_eq!(castles("2r5/8/8/8/8/8/8/R3K2R w KQ - 0 1"), [true, false]);
+        // The b1 square may be attacked, as the king never crosses it
+        assert_eq!(castles("1r6/8/8/8/8/8/8/R3K2R w KQ - 0 1"), [true, true]);
+
+        let mut game =
+            Game::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPP1/R3K2R w KQkq - 0 1").unwrap();
+        for uci in ["h1h2", "a7a6", "h2h1", "a6a5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(castles(&game.to_fen()), [false, true]);
+        for uci in ["e1d1", "a5a4", "d1e1", "a4a3"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(castles(&game.to_fen()), [false, false]);
+    }
+
+    #[test]
+    fn kings_and_rooks_give_up_their_rights() {
+        let start = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let rights_after = |uci: &[&str]| {
+            let mut game = Game::from_fen(start).unwrap();
+            for mv in uci {
+                game.make_uci_move(mv).unwrap();
+            }
+            let rights = game.castling_rights();
+            [
+                rights.white_king,
+                rights.white_queen,
+                rights.black_king,
+                rights.black_queen,
+            ]
+        };
+        assert_eq!(rights_after(&[]), [true; 4]);
+        assert_eq!(rights_after(&["e1e2"]), [false, false, true, true]);
+        assert_eq!(rights_after(&["a1a2"]), [true, false, true, true]);
+        assert_eq!(rights_after(&["h1h2"]), [false, true, true, true]);
+        // Taking a rook in its corner takes that right from the other side
+        assert_eq!(rights_after(&["a1a8"]), [true, false, true, false]);
+        assert_eq!(rights_after(&["e1f1", "h8h1"]), [false, false, false, true]);
+        // Coming back home does not bring a right back
+        assert_eq!(
+            rights_after(&["a1a2", "e8d8", "a2a1"]),
+            [true, false, false, false]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::error::Error;
+use std::fmt;
+
+use super::fen::piece_from_letter;
+use super::{
+    CastlingRights, Color, FenError, Game, ParseError, PieceType, Position, BOARD_DIMENSIONS,
+};
+
+/// Reasons a command typed during play could not be carried out
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CommandError {
+    /// The command needs more words, such as a square after "remove"
+    MissingArgument(&'static str),
+    InvalidPiece(String),
+    InvalidSquare(ParseError),
+    InvalidFen(FenError),
+    /// `undo` was given before any move had been played
+    NothingToUndo,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::MissingArgument(usage) => write!(f, "usage: {}", usage),
+            CommandError::InvalidPiece(piece) => write!(f, "'{}' is not a piece letter", piece),
+            CommandError::InvalidSquare(err) => write!(f, "{}", err),
+            CommandError::InvalidFen(err) => write!(f, "invalid FEN: {}", err),
+            CommandError::NothingToUndo => write!(f, "there is no move to take back"),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+/// Ways a position put together by hand cannot be played from
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PositionError {
+    /// Each side needs exactly one king. Holds the color and how many it has
+    KingCount(Color, usize),
+    /// Pawns can never stand on the first or last rank
+    PawnOnBackRank,
+    /// The side that just moved cannot have left its own king in check
+    WaitingSideInCheck,
+    /// A castling right is held without its king and rook on their squares. Holds the color
+    /// and whether it is the kingside right
+    CastlingWithoutPieces(Color, bool),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::KingCount(color, count) => {
+                write!(f, "{:?} has {} kings instead of one", color, count)
+            }
+            PositionError::PawnOnBackRank => write!(f, "a pawn is on the first or last rank"),
+            PositionError::WaitingSideInCheck => {
+                write!(f, "the side that is not to move is in check")
+            }
+            PositionError::CastlingWithoutPieces(color, kingside) => write!(
+                f,
+                "{:?} may castle {} but its king or rook is not in place",
+                color,
+                if *kingside { "kingside" } else { "queenside" }
+            ),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+impl Game {
+    /// Carries out a command typed during play, returning `Ok(false)` if `line` is not a
+    /// command so it can be read as a move instead. The commands are:
+    ///
+    /// - `setup <fen>` loads a position
+    /// - `clear` empties the board
+    /// - `put <piece> <square>` places a piece given by its FEN letter, such as `put N f3`
+    /// - `remove <square>` empties a square
+    /// - `undo` takes back the last move
+    ///
+    /// Editing the board starts a new game from the edited position, so moves played before it
+    /// can no longer be taken back. Display settings are kept
+    pub fn run_command(&mut self, line: &str) -> Result<bool, CommandError> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("setup") => {
+                let fen = words.collect::<Vec<_>>().join(" ");
+                let setup = Game::from_fen(&fen).map_err(CommandError::InvalidFen)?;
+                self.board = setup.board;
+                self.turn = setup.turn;
+                self.en_passant = setup.en_passant;
+                self.castling = setup.castling;
+                self.halfmove_clock = setup.halfmove_clock;
+                self.fullmove_number = setup.fullmove_number;
+            }
+            Some("clear") => {
+                self.board.state = [[None; BOARD_DIMENSIONS]; BOARD_DIMENSIONS];
+                self.en_passant = None;
+                self.castling = CastlingRights::default();
+            }
+            Some("put") => {
+                let usage = || CommandError::MissingArgument("put <piece> <square>");
+                let letter = words.next().ok_or_else(usage)?;
+                let square = words.next().ok_or_else(usage)?;
+                let pos = Position::from_algebraic(square).map_err(CommandError::InvalidSquare)?;
+                let mut chars = letter.chars();
+                let piece = match (chars.next(), chars.next()) {
+                    (Some(symbol), None) => piece_from_letter(symbol, pos),
+                    _ => None,
+                };
+                let piece = piece.ok_or_else(|| CommandError::InvalidPiece(letter.to_string()))?;
+                self.board.place(piece);
+                self.en_passant = None;
+                self.castling.drop_missing_pieces(&self.board);
+            }
+            Some("remove") => {
+                let square = words
+                    .next()
+                    .ok_or(CommandError::MissingArgument("remove <square>"))?;
+                let pos = Position::from_algebraic(square).map_err(CommandError::InvalidSquare)?;
+                self.board.clear(pos);
+                self.en_passant = None;
+                self.castling.drop_missing_pieces(&self.board);
+            }
+            // Taking a move back leaves the history of the moves before it alone
+            Some("undo") => {
+                self.undo().ok_or(CommandError::NothingToUndo)?;
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        }
+        self.history.clear();
+        self.claimed_draw = None;
+        self.material = self.count_material();
+        self.start_hashing();
+        Ok(true)
+    }
+
+    /// Checks that play can go on from the current position: one king each, no pawns on the
+    /// first or last rank, the side not to move not in check and a king and rook in place for
+    /// every castling right
+    pub fn validate_position(&self) -> Result<(), PositionError> {
+        for &color in &[Color::White, Color::Black] {
+            let kings = self.board.pieces_of(PieceType::King, color).len();
+            if kings != 1 {
+                return Err(PositionError::KingCount(color, kings));
+            }
+        }
+        let last_rank = BOARD_DIMENSIONS as i8 - 1;
+        if self.board.occupied_squares().any(|pos| {
+            self.board.piece_type_at(pos) == Some(PieceType::Pawn)
+                && (pos.x == 0 || pos.x == last_rank)
+        }) {
+            return Err(PositionError::PawnOnBackRank);
+        }
+        let waiting = self.turn.opposite();
+        if self.is_in_check(waiting) {
+            return Err(PositionError::WaitingSideInCheck);
+        }
+        for &color in &[Color::White, Color::Black] {
+            for &kingside in &[true, false] {
+                if self.castling.allows(color, kingside)
+                    && !self.castling.has_pieces_for(&self.board, color, kingside)
+                {
+                    return Err(PositionError::CastlingWithoutPieces(color, kingside));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn setup_loads_a_position_mid_game() {
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        assert_eq!(
+            game.run_command("setup 4k3/8/8/8/8/8/8/4K2R b K - 0 1"),
+            Ok(true)
+        );
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(
+            game.board.square(sq("h1")).map(|piece| piece.piece_type),
+            Some(PieceType::Rook)
+        );
+        assert!(game.board.square(sq("e4")).is_none());
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K2R b K - 0 1");
+        assert_eq!(game.run_command("undo"), Err(CommandError::NothingToUndo));
+        assert!(matches!(
+            game.run_command("setup 4k3/8 b"),
+            Err(CommandError::InvalidFen(_))
+        ));
+        assert_eq!(game.run_command("e7e5"), Ok(false));
+    }
+
+    #[test]
+    fn clear_put_and_remove_build_a_position() {
+        let mut game = Game::new();
+        assert_eq!(game.run_command("clear"), Ok(true));
+        assert_eq!(
+            game.validate_position(),
+            Err(PositionError::KingCount(Color::White, 0))
+        );
+        game.run_command("put K e1").unwrap();
+        game.run_command("put k e8").unwrap();
+        game.run_command("put P a8").unwrap();
+        assert_eq!(game.validate_position(), Err(PositionError::PawnOnBackRank));
+        game.run_command("remove a8").unwrap();
+        game.run_command("put R e7").unwrap();
+        assert_eq!(
+            game.validate_position(),
+            Err(PositionError::WaitingSideInCheck)
+        );
+        game.run_command("remove e7").unwrap();
+        assert_eq!(game.validate_position(), Ok(()));
+        assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+
+        assert_eq!(
+            game.run_command("put X e4"),
+            Err(CommandError::InvalidPiece("X".to_string()))
+        );
+        assert!(matches!(
+            game.run_command("put K z9"),
+            Err(CommandError::InvalidSquare(_))
+        ));
+        assert_eq!(
+            game.run_command("remove"),
+            Err(CommandError::MissingArgument("remove <square>"))
+        );
+    }
+
+    #[test]
+    fn editing_the_board_drops_castling_rights_it_breaks() {
+        let mut game = Game::new();
+        game.run_command("remove h1").unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1"
+        );
+        assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+        assert_eq!(game.validate_position(), Ok(()));
+        // Moving the king off its square by hand takes both of its rights
+        game.run_command("remove e8").unwrap();
+        game.run_command("put k e7").unwrap();
+        assert!(game.to_fen().contains(" w Q - "));
+        // A rook put back in its corner does not bring the right back
+        game.run_command("put R h1").unwrap();
+        assert!(game.to_fen().contains(" w Q - "));
+
+        // A right held by hand without its rook is caught before play goes on
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        game.board.clear(sq("a1"));
+        assert_eq!(
+            game.validate_position(),
+            Err(PositionError::CastlingWithoutPieces(Color::White, false))
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::cell::{Cell, RefCell};
+
+use super::search::{Search, SearchLimits};
+use super::{Game, Move};
+use crate::rng::{Rng, XorShiftRng};
+
+/// Anything that can pick moves for a side, such as a search or a random mover
+pub trait Engine {
+    /// A legal move for the side to move in `game`, or `None` when it has no legal moves
+    fn choose_move(&self, game: &Game) -> Option<Move>;
+}
+
+/// Plays a legal move chosen uniformly at random. The same seed always plays the same moves
+pub struct RandomEngine {
+    // choose_move only borrows the engine, so the generator is kept in a RefCell
+    rng: RefCell<XorShiftRng>,
+}
+
+impl RandomEngine {
+    pub fn new(seed: u64) -> RandomEngine {
+        RandomEngine {
+            rng: RefCell::new(XorShiftRng::new(seed)),
+        }
+    }
+}
+
+impl Engine for RandomEngine {
+    fn choose_move(&self, game: &Game) -> Option<Move> {
+        let moves = game.all_legal_moves(game.turn);
+        if moves.is_empty() {
+            return None;
+        }
+        Some(moves[self.rng.borrow_mut().below(moves.len())])
+    }
+}
+
+/// Searches every line `depth` moves deep and plays the one Game::evaluate scores best, which
+/// weighs piece placement as well as material. It runs the same search as Game::best_move:
+/// alpha-beta pruning skips lines that cannot change the choice, so it plays exactly the move a
+/// full search would, and past `depth` captures are searched until the position is quiet, so a
+/// line never stops halfway through an exchange
+pub struct MinimaxEngine {
+    limits: SearchLimits,
+    pruning: bool,
+    quiescence: bool,
+    /// Positions visited by the last search
+    nodes: Cell<u64>,
+}
+
+impl MinimaxEngine {
+    /// An engine looking `depth` moves ahead. A depth of 0 is treated as 1
+    pub fn new(depth: u32) -> MinimaxEngine {
+        MinimaxEngine::with_limits(SearchLimits::depth(depth))
+    }
+
+    /// An engine that stops at whichever of `limits` runs out first. A depth of 0 is treated
+    /// as 1
+    pub fn with_limits(limits: SearchLimits) -> MinimaxEngine {
+        MinimaxEngine {
+            limits: SearchLimits {
+                depth: limits.depth.max(1),
+                ..limits
+            },
+            pruning: true,
+            quiescence: true,
+            nodes: Cell::new(0),
+        }
+    }
+
+    /// An engine that searches every line to `depth` in full, for checking the pruned search
+    /// against
+    pub fn without_pruning(depth: u32) -> MinimaxEngine {
+        MinimaxEngine {
+            pruning: false,
+            ..MinimaxEngine::new(depth)
+        }
+    }
+
+    /// An engine that scores the position as it stands once `depth` moves are played, even in
+    /// the middle of an exchange
+    pub fn without_quiescence(depth: u32) -> MinimaxEngine {
+        MinimaxEngine {
+            quiescence: false,
+            ..MinimaxEngine::new(depth)
+        }
+    }
+
+    /// How many positions the last search visited
+    pub fn nodes(&self) -> u64 {
+        self.nodes.get()
+    }
+
+    /// The best move for the side to move along with its score in centipawns, or `None` when there
+    /// are no legal moves. Of moves that score the same, the first one generated is picked
+    pub fn search(&self, game: &Game) -> Option<(Move, i32)> {
+        let mut search = Search::new(self.limits);
+        search.pruning = self.pruning;
+        search.quiescence = self.quiescence;
+        let best = search.run(game);
+        self.nodes.set(search.nodes());
+        best
+    }
+}
+
+impl Engine for MinimaxEngine {
+    fn choose_move(&self, game: &Game) -> Option<Move> {
+        self.search(game).map(|(mv, _)| mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimax_weighs_piece_placement() {
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        game.make_uci_move("e7e5").unwrap();
+        let mv = MinimaxEngine::new(3).choose_move(&game).unwrap();
+        assert!(
+            !["b1a3", "g1h3"].contains(&mv.to_uci().as_str()),
+            "{}",
+            mv.to_uci()
+        );
+        let (_, score) = MinimaxEngine::new(1).search(&game).unwrap();
+        assert!(score > 0 && score < 100, "{}", score);
+    }
+
+    #[test]
+    fn minimax_runs_the_same_search_as_best_move() {
+        let game =
+            Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        for depth in 1..=2 {
+            let (mv, _) = MinimaxEngine::new(depth).search(&game).unwrap();
+            assert_eq!(Some(mv), game.best_move(SearchLimits::depth(depth)));
+        }
+        let engine = MinimaxEngine::with_limits(SearchLimits {
+            nodes: Some(500),
+            ..SearchLimits::depth(10)
+        });
+        assert!(engine.choose_move(&game).is_some());
+        assert!(engine.nodes() <= 500, "{}", engine.nodes());
+    }
+
+    #[test]
+    fn random_engine_plays_legal_moves_reproducibly() {
+        let mut rng = XorShiftRng::new(286);
+        let engine = RandomEngine::new(42);
+        for _ in 0..20 {
+            let game = Game::random_position(&mut rng);
+            let legal = game.legal_moves();
+            match engine.choose_move(&game) {
+                Some(mv) => assert!(legal.contains(&mv), "{}", game.to_fen()),
+                None => assert!(legal.is_empty(), "{}", game.to_fen()),
+            }
+        }
+        let stalemate = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(engine.choose_move(&stalemate), None);
+
+        // Two engines with the same seed play the same game, which is never refused a move
+        let play_out = |seed: u64| {
+            let engine = RandomEngine::new(seed);
+            let mut game = Game::new();
+            while game.result().is_none() && game.history.len() < 200 {
+                let mv = engine.choose_move(&game).unwrap();
+                game.make_move(mv).unwrap();
+            }
+            game.to_fen()
+        };
+        assert_eq!(play_out(7), play_out(7));
+        assert_ne!(play_out(7), play_out(8));
+    }
+
+    #[test]
+    fn minimax_takes_a_hanging_queen() {
+        let best = |fen: &str, depth: u32| {
+            let game = Game::from_fen(fen).unwrap();
+            MinimaxEngine::new(depth)
+                .choose_move(&game)
+                .unwrap()
+                .to_uci()
+        };
+        assert_eq!(best("4k3/8/8/3q4/8/2N5/8/4K3 w - - 0 1", 1), "c3d5");
+        // Black takes with the pawn
+        assert_eq!(best("4k3/8/4p3/3Q4/8/8/8/4K3 b - - 0 1", 2), "e6d5");
+        // Mate in one beats any material
+        assert_eq!(best("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 1), "a1a8");
+    }
+
+    #[test]
+    fn pruning_changes_nothing_but_the_work() {
+        for (fen, depth) in [
+            (
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+                2,
+            ),
+            ("4k3/2p5/3p4/8/8/8/8/3QK3 w - - 0 1", 2),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3),
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            let pruned = MinimaxEngine::new(depth);
+            let full = MinimaxEngine::without_pruning(depth);
+            assert_eq!(pruned.search(&game), full.search(&game), "{}", fen);
+            assert!(
+                pruned.nodes() < full.nodes(),
+                "{}: {} against {}",
+                fen,
+                pruned.nodes(),
+                full.nodes()
+            );
+        }
+    }
+
+    #[test]
+    fn quiescence_sees_the_recapture_past_the_horizon() {
+        // At depth 2 the queen takes on e4 with the last move, and without looking further the
+        // pawn seems lost whatever White does. The pawn on d3 takes the queen back
+        let game = Game::from_fen("6k1/4q3/8/8/4P3/3P4/8/6K1 w - - 0 1").unwrap();
+        let (_, plain) = MinimaxEngine::without_quiescence(2).search(&game).unwrap();
+        let (_, quiet) = MinimaxEngine::new(2).search(&game).unwrap();
+        assert!(quiet - plain >= 80, "{} against {}", quiet, plain);
+
+        // The pawn on d5 is defended, so taking it gives the queen away
+        let game = Game::from_fen("6k1/8/2p5/3p4/8/8/8/3Q2K1 w - - 0 1").unwrap();
+        let (greedy, _) = MinimaxEngine::without_quiescence(1).search(&game).unwrap();
+        assert_eq!(greedy.to_uci(), "d1d5");
+        let (mv, score) = MinimaxEngine::new(1).search(&game).unwrap();
+        assert_ne!(mv.to_uci(), "d1d5");
+        assert!(score > 0 && score < 800, "{}", score);
+    }
+}
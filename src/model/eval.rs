@@ -0,0 +1,320 @@
+use super::{Color, Game, PieceType, Position, BOARD_DIMENSIONS};
+
+/// Bonus for a passed pawn, indexed by how many ranks it has advanced from its starting rank
+const PASSED_PAWN_BONUS: [i32; BOARD_DIMENSIONS] = [0, 0, 10, 20, 35, 60, 100, 0];
+
+/// Bonus for each rook on the opponent's second rank
+const ROOK_ON_SEVENTH_BONUS: i32 = 25;
+
+/// King tropism bonus for a piece right next to the enemy king, shrinking in proportion to the
+/// distance so that a piece twice as far away earns half as much
+const KING_TROPISM_SCALE: i32 = 12;
+
+/// Piece-square table, a bonus or penalty in centipawns for each square. Laid out the way the
+/// board is printed for White, eighth rank first, and mirrored for Black
+type PieceSquareTable = [[i32; BOARD_DIMENSIONS]; BOARD_DIMENSIONS];
+
+/// Pawns are worth more as they advance, especially in the center, and shielding the castled
+/// king
+#[rustfmt::skip]
+const PAWN_TABLE: PieceSquareTable = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [ 50,  50,  50,  50,  50,  50,  50,  50],
+    [ 10,  10,  20,  30,  30,  20,  10,  10],
+    [  5,   5,  10,  25,  25,  10,   5,   5],
+    [  0,   0,   0,  20,  20,   0,   0,   0],
+    [  5,  -5, -10,   0,   0, -10,  -5,   5],
+    [  5,  10,  10, -20, -20,  10,  10,   5],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+/// Knights reach the most squares from the center and the fewest from the rim
+#[rustfmt::skip]
+const KNIGHT_TABLE: PieceSquareTable = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20,   0,   0,   0,   0, -20, -40],
+    [-30,   0,  10,  15,  15,  10,   0, -30],
+    [-30,   5,  15,  20,  20,  15,   5, -30],
+    [-30,   0,  15,  20,  20,  15,   0, -30],
+    [-30,   5,  10,  15,  15,  10,   5, -30],
+    [-40, -20,   0,   5,   5,   0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+/// Bishops avoid corners and edges
+#[rustfmt::skip]
+const BISHOP_TABLE: PieceSquareTable = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,  10,  10,   5,   0, -10],
+    [-10,   5,   5,  10,  10,   5,   5, -10],
+    [-10,   0,  10,  10,  10,  10,   0, -10],
+    [-10,  10,  10,  10,  10,  10,  10, -10],
+    [-10,   5,   0,   0,   0,   0,   5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+/// Rooks like the seventh rank and the center files of their own back rank
+#[rustfmt::skip]
+const ROOK_TABLE: PieceSquareTable = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [  5,  10,  10,  10,  10,  10,  10,   5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [  0,   0,   0,   5,   5,   0,   0,   0],
+];
+
+/// Queens do a little better in the center
+#[rustfmt::skip]
+const QUEEN_TABLE: PieceSquareTable = [
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [ -5,   0,   5,   5,   5,   5,   0,  -5],
+    [  0,   0,   5,   5,   5,   5,   0,  -5],
+    [-10,   5,   5,   5,   5,   5,   0, -10],
+    [-10,   0,   5,   0,   0,   0,   0, -10],
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+];
+
+/// In the middlegame the king stays back, ideally castled behind its pawns
+#[rustfmt::skip]
+const KING_TABLE: PieceSquareTable = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [ 20,  20,   0,   0,   0,   0,  20,  20],
+    [ 20,  30,  10,   0,   0,  10,  30,  20],
+];
+
+/// Bonus from the piece-square table of `piece_type` for a piece of `color` on `pos`
+fn piece_square_bonus(piece_type: PieceType, color: Color, pos: Position) -> i32 {
+    let table = match piece_type {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => &KING_TABLE,
+    };
+    // The tables list the eighth rank first, which is Black's first rank
+    let row = match color {
+        Color::White => BOARD_DIMENSIONS - 1 - pos.x as usize,
+        Color::Black => pos.x as usize,
+    };
+    table[row][pos.y as usize]
+}
+
+impl Game {
+    /// Static evaluation of the position in centipawns from the point of view of the side to
+    /// move, so a positive score is good for whoever is about to play
+    pub fn evaluate(&self) -> i32 {
+        let score =
+            self.material_balance() + self.side_score(Color::White) - self.side_score(Color::Black);
+        match self.turn {
+            Color::Black => -score,
+            _ => score,
+        }
+    }
+
+    /// White's material minus Black's in centipawns. This is kept up to date as moves are made
+    /// and taken back rather than recounted
+    pub fn material_balance(&self) -> i32 {
+        self.material
+    }
+
+    /// Counts White's material minus Black's from scratch
+    pub(super) fn count_material(&self) -> i32 {
+        self.board
+            .iter_pieces()
+            .map(|(_, piece)| match piece.color {
+                Color::Black => -piece.piece_type.value(),
+                _ => piece.piece_type.value(),
+            })
+            .sum()
+    }
+
+    /// Everything besides material the evaluation credits to one color
+    fn side_score(&self, color: Color) -> i32 {
+        let mut score = 0;
+        for (pos, piece) in self.board.pieces(color) {
+            score += piece_square_bonus(piece.piece_type, color, pos);
+            if self.is_passed_pawn(pos) {
+                let advanced = match color {
+                    Color::Black => BOARD_DIMENSIONS as i8 - 1 - pos.x,
+                    _ => pos.x,
+                };
+                score += PASSED_PAWN_BONUS[advanced as usize];
+            }
+        }
+        score + self.rook_on_seventh(color) + self.king_tropism(color)
+    }
+
+    /// How closely the given color's pieces crowd the enemy king: each piece other than pawns
+    /// and the king adds a bonus inversely proportional to its distance in king steps
+    pub fn king_tropism(&self, color: Color) -> i32 {
+        let enemy = color.opposite();
+        let king = match self.board.find_king(enemy) {
+            Some(king) => king,
+            None => return 0,
+        };
+        self.board
+            .pieces(color)
+            .filter(|(_, piece)| {
+                piece.piece_type != PieceType::Pawn && piece.piece_type != PieceType::King
+            })
+            .map(|(pos, _)| {
+                let distance = (pos.x - king.x).abs().max((pos.y - king.y).abs());
+                KING_TROPISM_SCALE / distance as i32
+            })
+            .sum()
+    }
+
+    /// Bonus for the given color's rooks standing on the seventh rank from its own side of the
+    /// board, where they attack pawns that have not moved and hem in the enemy king
+    pub fn rook_on_seventh(&self, color: Color) -> i32 {
+        let seventh = match color {
+            Color::Black => 1,
+            _ => BOARD_DIMENSIONS - 2,
+        };
+        let rooks = self.board.state[seventh]
+            .iter()
+            .flatten()
+            .filter(|piece| piece.piece_type == PieceType::Rook && piece.color == color)
+            .count();
+        rooks as i32 * ROOK_ON_SEVENTH_BONUS
+    }
+
+    /// Returns true if `pos` holds a pawn that no enemy pawn can block or capture on its way to
+    /// promotion, meaning there are no enemy pawns ahead of it on its own or adjacent files
+    pub fn is_passed_pawn(&self, pos: Position) -> bool {
+        let pawn = match self.board.square(pos) {
+            Some(pawn) if pawn.piece_type == PieceType::Pawn => pawn,
+            _ => return false,
+        };
+        let ahead = |x: i8| match pawn.color {
+            Color::Black => x < pos.x,
+            _ => x > pos.x,
+        };
+        !self.board.iter_pieces().any(|(other, piece)| {
+            piece.piece_type == PieceType::Pawn
+                && piece.color != pawn.color
+                && (other.y - pos.y).abs() <= 1
+                && ahead(other.x)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn passed_pawns_have_no_enemy_pawns_ahead() {
+        let game = Game::new();
+        assert_eq!(game.evaluate(), 0);
+        assert!(!game.is_passed_pawn(sq("e2")));
+
+        let game = Game::from_fen("4k3/p7/8/8/8/8/3PPP2/4K3 w - - 0 1").unwrap();
+        assert!(game.is_passed_pawn(sq("e2")));
+        assert!(game.is_passed_pawn(sq("d2")));
+        assert!(!game.is_passed_pawn(sq("e1")));
+        assert!(game.is_passed_pawn(sq("a7")));
+
+        // A pawn on an adjacent file ahead of it can still capture it
+        let game = Game::from_fen("4k3/8/8/3p4/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert!(!game.is_passed_pawn(sq("e2")));
+        assert!(!game.is_passed_pawn(sq("d5")));
+    }
+
+    #[test]
+    fn rooks_on_the_seventh_earn_a_bonus() {
+        let low = Game::from_fen("4k3/8/8/8/8/R7/8/4K3 w - - 0 1").unwrap();
+        let seventh = Game::from_fen("4k3/R7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(low.rook_on_seventh(Color::White), 0);
+        assert_eq!(seventh.rook_on_seventh(Color::White), ROOK_ON_SEVENTH_BONUS);
+        assert!(seventh.evaluate() > low.evaluate());
+
+        // Black's seventh rank is the second
+        let black = Game::from_fen("4k3/8/8/8/8/8/r6r/4K3 b - - 0 1").unwrap();
+        assert_eq!(
+            black.rook_on_seventh(Color::Black),
+            2 * ROOK_ON_SEVENTH_BONUS
+        );
+        assert_eq!(black.rook_on_seventh(Color::White), 0);
+    }
+
+    #[test]
+    fn material_is_tracked_through_make_and_unmake() {
+        let mut game = Game::new();
+        for uci in ["e2e4", "d7d5", "e4d5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(game.material_balance(), 100);
+        assert_eq!(game.material_balance(), game.count_material());
+        game.unmake_move();
+        assert_eq!(game.material_balance(), 0);
+
+        // Promoting swaps a pawn for the new piece
+        let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_uci_move("a7a8n").unwrap();
+        assert_eq!(game.material_balance(), PieceType::Knight.value());
+        assert_eq!(game.material_balance(), game.count_material());
+        game.unmake_move();
+        assert_eq!(game.material_balance(), PieceType::Pawn.value());
+    }
+
+    #[test]
+    fn pieces_near_the_enemy_king_raise_tropism() {
+        let far = Game::from_fen("6k1/8/8/8/8/8/8/NN2K3 w - - 0 1").unwrap();
+        let near = Game::from_fen("6k1/8/5NN1/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(near.king_tropism(Color::White) > far.king_tropism(Color::White));
+        // Two knights two steps from g8
+        assert_eq!(
+            near.king_tropism(Color::White),
+            2 * (KING_TROPISM_SCALE / 2)
+        );
+        // Kings and pawns do not count
+        assert_eq!(near.king_tropism(Color::Black), 0);
+        let pawns = Game::from_fen("6k1/5PP1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(pawns.king_tropism(Color::White), 0);
+        assert!(near.evaluate() > far.evaluate());
+    }
+
+    #[test]
+    fn piece_square_tables_favor_central_knights_and_advanced_pawns() {
+        let evaluate = |fen: &str| Game::from_fen(fen).unwrap().evaluate();
+        assert!(
+            evaluate("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1")
+                > evaluate("4k3/8/8/8/8/8/8/N3K3 w - - 0 1")
+        );
+        // The tables are mirrored for Black, so the same knight scores the same for its side
+        assert_eq!(
+            evaluate("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1"),
+            evaluate("4k3/8/8/3n4/8/8/8/4K3 b - - 0 1")
+        );
+        assert_eq!(Game::new().evaluate(), 0);
+        assert_eq!(
+            piece_square_bonus(PieceType::Pawn, Color::White, sq("e4")),
+            piece_square_bonus(PieceType::Pawn, Color::Black, sq("e5"))
+        );
+        assert!(
+            piece_square_bonus(PieceType::Pawn, Color::White, sq("e6"))
+                > piece_square_bonus(PieceType::Pawn, Color::White, sq("e3"))
+        );
+        assert!(
+            piece_square_bonus(PieceType::King, Color::White, sq("g1"))
+                > piece_square_bonus(PieceType::King, Color::White, sq("e4"))
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use std::error::Error;
+use std::fmt;
+
+use super::{Board, CastlingRights, Color, Game, Piece, PieceType, Position, BOARD_DIMENSIONS};
+
+/// The ways a FEN string can be malformed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FenError {
+    /// A FEN record has six space separated fields
+    WrongFieldCount(usize),
+    /// The piece placement field must describe eight ranks
+    WrongRankCount(usize),
+    /// A rank (numbered 8 down to 1) describes more or fewer than eight squares
+    BadRankLength {
+        rank: usize,
+        squares: usize,
+    },
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidCounter(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "expected 6 fields, found {}", count),
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::BadRankLength { rank, squares } => {
+                write!(
+                    f,
+                    "rank {} describes {} squares instead of 8",
+                    rank, squares
+                )
+            }
+            FenError::InvalidPiece(piece) => write!(f, "'{}' is not a piece", piece),
+            FenError::InvalidSideToMove(side) => write!(f, "invalid side to move '{}'", side),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "invalid castling availability '{}'", castling)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square '{}'", square)
+            }
+            FenError::InvalidCounter(counter) => write!(f, "invalid move counter '{}'", counter),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+/// Checks that `fen` is well formed without building a game from it. This covers the field
+/// count, the length of every rank, the piece letters, the side to move token, castling and en
+/// passant fields and both move counters
+pub fn validate_fen(fen: &str) -> Result<(), FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Err(FenError::WrongFieldCount(fields.len()));
+    }
+    validate_placement(fields[0])?;
+
+    if fields[1] != "w" && fields[1] != "b" {
+        return Err(FenError::InvalidSideToMove(fields[1].to_string()));
+    }
+
+    // Castling rights come from KQkq, in that order, each at most once
+    let castling = fields[2];
+    let mut remaining = "KQkq".chars();
+    if castling.is_empty()
+        || (castling != "-" && !castling.chars().all(|right| remaining.any(|c| c == right)))
+    {
+        return Err(FenError::InvalidCastling(castling.to_string()));
+    }
+
+    let en_passant = fields[3];
+    if en_passant != "-" {
+        let square: Vec<char> = en_passant.chars().collect();
+        if square.len() != 2
+            || !('a'..='h').contains(&square[0])
+            || (square[1] != '3' && square[1] != '6')
+        {
+            return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+        }
+    }
+
+    match fields[4].parse::<u32>() {
+        Ok(_) => {}
+        Err(_) => return Err(FenError::InvalidCounter(fields[4].to_string())),
+    }
+    match fields[5].parse::<u32>() {
+        Ok(fullmove) if fullmove >= 1 => {}
+        _ => return Err(FenError::InvalidCounter(fields[5].to_string())),
+    }
+    Ok(())
+}
+
+/// Checks the piece placement field: eight ranks of eight squares using only piece letters and
+/// runs of empty squares
+fn validate_placement(placement: &str) -> Result<(), FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != BOARD_DIMENSIONS {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+    for (index, rank) in ranks.iter().enumerate() {
+        let mut squares = 0;
+        for symbol in rank.chars() {
+            match symbol.to_digit(10) {
+                Some(empty) if (1..=8).contains(&empty) => squares += empty as usize,
+                _ if "pnbrqkPNBRQK".contains(symbol) => squares += 1,
+                _ => return Err(FenError::InvalidPiece(symbol)),
+            }
+        }
+        if squares != BOARD_DIMENSIONS {
+            return Err(FenError::BadRankLength {
+                rank: BOARD_DIMENSIONS - index,
+                squares,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Names of the FEN fields after piece placement, in order
+const FIELD_NAMES: [&str; 5] = [
+    "side to move",
+    "castling",
+    "en passant",
+    "halfmove clock",
+    "fullmove number",
+];
+
+/// Describes every square and field that differs between two FEN records, such as
+/// "e4: P vs empty" or "castling: KQkq vs Kq", for finding where a round trip went wrong. The
+/// records are not validated, so a malformed one is compared as far as it goes
+pub fn fen_diff(a: &str, b: &str) -> Vec<String> {
+    let fields_a: Vec<&str> = a.split_whitespace().collect();
+    let fields_b: Vec<&str> = b.split_whitespace().collect();
+    let mut differences = Vec::new();
+
+    let squares_a = expand_placement(fields_a.first().copied().unwrap_or(""));
+    let squares_b = expand_placement(fields_b.first().copied().unwrap_or(""));
+    for index in 0..squares_a.len().max(squares_b.len()) {
+        let square_a = squares_a.get(index).copied().flatten();
+        let square_b = squares_b.get(index).copied().flatten();
+        if square_a != square_b {
+            // Placement runs from a8 across and down to h1
+            let square = Position {
+                x: (BOARD_DIMENSIONS - 1 - index / BOARD_DIMENSIONS) as i8,
+                y: (index % BOARD_DIMENSIONS) as i8,
+            };
+            let name = if square.is_on_board() {
+                square.to_algebraic()
+            } else {
+                format!("square {}", index + 1)
+            };
+            let describe = |symbol: Option<char>| symbol.map_or("empty".to_string(), String::from);
+            differences.push(format!(
+                "{}: {} vs {}",
+                name,
+                describe(square_a),
+                describe(square_b)
+            ));
+        }
+    }
+
+    for (index, name) in FIELD_NAMES.iter().enumerate() {
+        let field_a = fields_a.get(index + 1).copied().unwrap_or("missing");
+        let field_b = fields_b.get(index + 1).copied().unwrap_or("missing");
+        if field_a != field_b {
+            differences.push(format!("{}: {} vs {}", name, field_a, field_b));
+        }
+    }
+    differences
+}
+
+/// The piece letter on every square named by a placement field, from a8 to h1, with `None` for
+/// empty squares
+fn expand_placement(placement: &str) -> Vec<Option<char>> {
+    let mut squares = Vec::new();
+    for symbol in placement.chars().filter(|&symbol| symbol != '/') {
+        match symbol.to_digit(10) {
+            Some(empty) => squares.extend((0..empty).map(|_| None)),
+            None => squares.push(Some(symbol)),
+        }
+    }
+    squares
+}
+
+/// The piece a FEN letter stands for, standing on `pos`: upper case for White and lower case
+/// for Black. Pawns are only allowed their two-square advance on their starting rank
+pub(super) fn piece_from_letter(letter: char, pos: Position) -> Option<Piece> {
+    let piece_type = match letter.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let mut piece = Piece::new(piece_type, color, pos);
+    if piece_type == PieceType::Pawn {
+        piece.first_move = match color {
+            Color::Black => pos.x == BOARD_DIMENSIONS as i8 - 2,
+            _ => pos.x == 1,
+        };
+    }
+    Some(piece)
+}
+
+impl Board {
+    /// Sets up a board from the piece placement field of a FEN record, such as
+    /// "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR". A full record is accepted too, in which
+    /// case everything after the placement is ignored
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let placement = fen.split_whitespace().next().unwrap_or("");
+        validate_placement(placement)?;
+        let mut board = Board {
+            state: [[None; BOARD_DIMENSIONS]; BOARD_DIMENSIONS],
+        };
+
+        // Ranks are listed from the eighth down to the first
+        for (index, rank) in placement.split('/').enumerate() {
+            let x = (BOARD_DIMENSIONS - 1 - index) as i8;
+            let mut y = 0;
+            for symbol in rank.chars() {
+                if let Some(empty) = symbol.to_digit(10) {
+                    y += empty as i8;
+                    continue;
+                }
+                // Validation guarantees every other character is a piece letter
+                if let Some(piece) = piece_from_letter(symbol, Position { x, y }) {
+                    board.place(piece);
+                }
+                y += 1;
+            }
+        }
+        Ok(board)
+    }
+
+    /// Writes the piece placement field of a FEN record, from the eighth rank down to the first
+    pub fn to_fen(&self) -> String {
+        let mut ranks = Vec::with_capacity(BOARD_DIMENSIONS);
+        for rank in self.state.iter().rev() {
+            let mut field = String::new();
+            let mut empty = 0;
+            for square in rank.iter() {
+                match square {
+                    Some(piece) => {
+                        if empty > 0 {
+                            field.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        field.push(piece.board_rep());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                field.push_str(&empty.to_string());
+            }
+            ranks.push(field);
+        }
+        ranks.join("/")
+    }
+}
+
+impl Game {
+    /// Sets up a game from a FEN record
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        validate_fen(fen)?;
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let mut game = Game::empty();
+        game.board = Board::from_fen(fields[0])?;
+
+        if fields[1] == "b" {
+            game.turn = Color::Black;
+        }
+        game.castling = CastlingRights {
+            white_king: fields[2].contains('K'),
+            white_queen: fields[2].contains('Q'),
+            black_king: fields[2].contains('k'),
+            black_queen: fields[2].contains('q'),
+            ..CastlingRights::default()
+        };
+        game.castling.locate_rooks(&game.board);
+        game.en_passant = Position::from_algebraic(fields[3]).ok();
+        game.halfmove_clock = fields[4].parse().unwrap_or(0);
+        game.fullmove_number = fields[5].parse().unwrap_or(1);
+        game.material = game.count_material();
+        game.start_hashing();
+        Ok(game)
+    }
+
+    /// Writes the position as a FEN record, which from_fen reads back to the same position
+    pub fn to_fen(&self) -> String {
+        let side = match self.turn {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let rights = [
+            (self.castling.white_king, 'K'),
+            (self.castling.white_queen, 'Q'),
+            (self.castling.black_king, 'k'),
+            (self.castling.black_queen, 'q'),
+        ];
+        let mut castling: String = rights
+            .iter()
+            .filter(|(held, _)| *held)
+            .map(|&(_, letter)| letter)
+            .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let en_passant = self
+            .en_passant
+            .map_or("-".to_string(), |square| square.to_algebraic());
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.to_fen(),
+            side,
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_coordinate_move;
+    use super::*;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn validate_fen_checks_every_field() {
+        assert_eq!(validate_fen(START), Ok(()));
+        assert_eq!(
+            validate_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            Ok(())
+        );
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/4K2k w Kq - 0 1"), Ok(()));
+
+        let broken = [
+            ("8/8/8/8/8/8/8/8 w - - 0", FenError::WrongFieldCount(5)),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::WrongRankCount(7)),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::BadRankLength {
+                    rank: 7,
+                    squares: 9,
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+                FenError::InvalidPiece('X'),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w QK - 0 1",
+                FenError::InvalidCastling("QK".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+                FenError::InvalidEnPassant("e4".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+                FenError::InvalidCounter("0".to_string()),
+            ),
+        ];
+        for (fen, error) in broken.iter() {
+            assert_eq!(validate_fen(fen).as_ref(), Err(error), "{}", fen);
+            assert_eq!(Game::from_fen(fen).err().as_ref(), Some(error), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn fen_diff_names_each_difference() {
+        let differences = fen_diff(
+            START,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPPPPPP/RNBQKBNR w Kq - 0 1",
+        );
+        assert_eq!(
+            differences,
+            vec![
+                "e4: empty vs P".to_string(),
+                "castling: KQkq vs Kq".to_string()
+            ]
+        );
+        assert!(fen_diff(START, START).is_empty());
+        assert_eq!(
+            fen_diff(
+                START,
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+            ),
+            vec![
+                "halfmove clock: 0 vs missing".to_string(),
+                "fullmove number: 1 vs missing".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn board_from_fen_reads_the_placement() {
+        let start = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+        assert!(start == Game::new().board);
+        assert!(Board::from_fen(START).unwrap() == start);
+
+        let midgame = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 5 4";
+        let board = Board::from_fen(midgame).unwrap();
+        let knight = board
+            .square(Position::from_algebraic("c6").unwrap())
+            .unwrap();
+        assert_eq!(knight.board_rep(), 'n');
+        assert!(board.is_pseudo_legal(knight, parse_coordinate_move("c6d4").unwrap()));
+        let game = Game::from_fen(midgame).unwrap();
+        assert_eq!(game.turn(), Color::Black);
+
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/7").err(),
+            Some(FenError::BadRankLength {
+                rank: 1,
+                squares: 7
+            })
+        );
+    }
+
+    #[test]
+    fn to_fen_is_read_back_unchanged() {
+        for fen in [
+            START,
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            "8/8/4k3/8/2K5/8/8/8 b - - 12 57",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w Kq d6 0 3",
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            assert_eq!(game.to_fen(), fen);
+            assert!(Board::from_fen(&game.board.to_fen()).unwrap() == game.board);
+        }
+
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        game.make_uci_move("g8f6").unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+        );
+        game.unmake_move();
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use super::{Color, Game, Piece, PieceType, Position, BOARD_DIMENSIONS};
+use crate::rng::Rng;
+
+/// Most pieces placed besides the two kings in a random position
+const MAX_EXTRA_PIECES: usize = 12;
+
+/// Pieces other than kings that may be placed in a random position
+const EXTRA_PIECE_TYPES: [PieceType; 5] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+];
+
+impl Game {
+    /// Builds a random position for fuzzing move generation. The position is always legal: each
+    /// side has exactly one king, the kings are not adjacent, no pawn stands on the first or
+    /// last rank and the side that just moved is not left in check
+    pub fn random_position(rng: &mut impl Rng) -> Game {
+        loop {
+            let mut game = Game::empty();
+            if rng.below(2) == 0 {
+                game.turn = Color::Black;
+            }
+
+            let white_king = random_square(rng);
+            let black_king = random_square(rng);
+            if (white_king.x - black_king.x).abs() <= 1 && (white_king.y - black_king.y).abs() <= 1
+            {
+                continue;
+            }
+            game.board
+                .place(Piece::new(PieceType::King, Color::White, white_king));
+            game.board
+                .place(Piece::new(PieceType::King, Color::Black, black_king));
+
+            for _ in 0..rng.below(MAX_EXTRA_PIECES + 1) {
+                let piece_type = EXTRA_PIECE_TYPES[rng.below(EXTRA_PIECE_TYPES.len())];
+                let color = if rng.below(2) == 0 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let pos = random_square(rng);
+                let last_rank = BOARD_DIMENSIONS as i8 - 1;
+                if !game.board.is_empty(pos)
+                    || (piece_type == PieceType::Pawn && (pos.x == 0 || pos.x == last_rank))
+                {
+                    continue;
+                }
+                let mut piece = Piece::new(piece_type, color, pos);
+                // Only pawns still on their starting rank may advance two squares
+                piece.first_move = piece_type == PieceType::Pawn
+                    && pos.x
+                        == match color {
+                            Color::Black => last_rank - 1,
+                            _ => 1,
+                        };
+                game.board.place(piece);
+            }
+
+            game.material = game.count_material();
+            let waiting = game.turn.opposite();
+            if !game.is_in_check(waiting) {
+                game.start_hashing();
+                return game;
+            }
+        }
+    }
+}
+
+fn random_square(rng: &mut impl Rng) -> Position {
+    Position {
+        x: rng.below(BOARD_DIMENSIONS) as i8,
+        y: rng.below(BOARD_DIMENSIONS) as i8,
+    }
+}
+
+/// Plays `plies` random legal moves starting from random positions and panics if taking a move
+/// back with unmake_move does not restore the board, side to move, en passant square, halfmove
+/// clock, FEN record and Zobrist hash exactly, or if the incrementally updated hash ever differs
+/// from a full recompute. A new random position is generated whenever the game ends. The run is
+/// fully determined by the state of `rng`
+#[cfg(test)]
+fn fuzz_check(rng: &mut impl Rng, plies: usize) {
+    let mut game = Game::random_position(rng);
+    for ply in 0..plies {
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            game = Game::random_position(rng);
+            continue;
+        }
+        let mv = moves[rng.below(moves.len())];
+        let board = game.board.clone();
+        let turn = game.turn;
+        let en_passant = game.en_passant;
+        let halfmove_clock = game.halfmove_clock;
+        let hash = game.zobrist_hash();
+        let fen = game.to_fen();
+
+        game.make_move(mv)
+            .unwrap_or_else(|err| panic!("ply {}: generated move rejected: {}", ply, err));
+        assert!(
+            game.zobrist_hash() == game.full_zobrist_hash(),
+            "ply {}: incremental hash differs from a full recompute",
+            ply
+        );
+        game.unmake_move();
+        assert!(
+            game.board == board,
+            "ply {}: unmake did not restore the board",
+            ply
+        );
+        assert!(
+            game.turn == turn,
+            "ply {}: unmake did not restore the turn",
+            ply
+        );
+        assert!(
+            game.en_passant == en_passant,
+            "ply {}: unmake did not restore the en passant square",
+            ply
+        );
+        assert!(
+            game.halfmove_clock == halfmove_clock,
+            "ply {}: unmake did not restore the halfmove clock",
+            ply
+        );
+        assert!(
+            game.to_fen() == fen,
+            "ply {}: unmake did not restore the FEN record",
+            ply
+        );
+        assert!(
+            game.zobrist_hash() == hash,
+            "ply {}: unmake did not restore the hash",
+            ply
+        );
+        assert!(
+            game.material_balance() == game.count_material(),
+            "ply {}: material balance drifted from a full recount",
+            ply
+        );
+
+        game.make_move(mv)
+            .unwrap_or_else(|err| panic!("ply {}: generated move rejected: {}", ply, err));
+        assert!(
+            game.zobrist_hash() == game.full_zobrist_hash(),
+            "ply {}: incremental hash differs from a full recompute",
+            ply
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShiftRng;
+
+    #[test]
+    fn make_and_unmake_hold_their_invariants() {
+        fuzz_check(&mut XorShiftRng::new(0x00C0_FFEE), 3000);
+    }
+
+    #[test]
+    fn random_positions_are_legal_and_reproducible() {
+        let mut rng = XorShiftRng::new(7);
+        let mut again = XorShiftRng::new(7);
+        for _ in 0..200 {
+            let game = Game::random_position(&mut rng);
+            assert_eq!(game.validate_position(), Ok(()));
+            assert_eq!(game.to_fen(), Game::random_position(&mut again).to_fen());
+        }
+    }
+
+    #[test]
+    fn random_positions_keep_pawns_off_the_back_ranks() {
+        let mut rng = XorShiftRng::new(11);
+        for _ in 0..200 {
+            let game = Game::random_position(&mut rng);
+            for (pos, piece) in game.board.iter_pieces() {
+                assert!(piece.position == pos);
+                if piece.piece_type == PieceType::Pawn {
+                    assert!(pos.x != 0 && pos.x != BOARD_DIMENSIONS as i8 - 1);
+                }
+            }
+            assert!(!game.is_in_check(game.turn.opposite()));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use super::Game;
+
+/// Known openings as the moves that define them, in coordinate notation. When several entries
+/// match a game the longest one is the most specific name
+const OPENINGS: [(&str, &str); 22] = [
+    ("e2e4", "King's Pawn Opening"),
+    ("d2d4", "Queen's Pawn Opening"),
+    ("c2c4", "English Opening"),
+    ("g1f3", "Reti Opening"),
+    ("e2e4 e7e5", "King's Pawn Game"),
+    ("e2e4 c7c5", "Sicilian Defence"),
+    ("e2e4 e7e6", "French Defence"),
+    ("e2e4 c7c6", "Caro-Kann Defence"),
+    ("e2e4 d7d5", "Scandinavian Defence"),
+    ("e2e4 e7e5 g1f3", "King's Knight Opening"),
+    ("e2e4 e7e5 f2f4", "King's Gambit"),
+    ("e2e4 e7e5 g1f3 g8f6", "Petrov's Defence"),
+    ("e2e4 e7e5 g1f3 b8c6 f1b5", "Ruy Lopez"),
+    ("e2e4 e7e5 g1f3 b8c6 f1c4", "Italian Game"),
+    ("e2e4 e7e5 g1f3 b8c6 d2d4", "Scotch Game"),
+    ("d2d4 d7d5 c2c4", "Queen's Gambit"),
+    ("d2d4 d7d5 c2c4 e7e6", "Queen's Gambit Declined"),
+    ("d2d4 d7d5 c2c4 d5c4", "Queen's Gambit Accepted"),
+    ("d2d4 d7d5 c2c4 c7c6", "Slav Defence"),
+    ("d2d4 g8f6", "Indian Defence"),
+    ("d2d4 g8f6 c2c4 g7g6", "King's Indian Defence"),
+    ("d2d4 f7f5", "Dutch Defence"),
+];
+
+impl Game {
+    /// Name of the opening the moves played so far belong to, or `None` if the game left the
+    /// known lines before any of them was reached. Games that did not begin at move 1 from the
+    /// standard starting position have no opening name
+    pub fn opening_name(&self) -> Option<&str> {
+        // Every ply played has a record, so a game begun at move 1 is now at this move number
+        let from_start = self.position_hashes.first() == Some(&Game::new().zobrist_hash())
+            && self.fullmove_number as usize == 1 + self.history.len() / 2;
+        if !from_start {
+            return None;
+        }
+        let played: Vec<String> = self
+            .history
+            .iter()
+            .map(|record| record.mv.start.to_algebraic() + &record.mv.end.to_algebraic())
+            .collect();
+        OPENINGS
+            .iter()
+            .filter(|(moves, _)| {
+                let line: Vec<&str> = moves.split(' ').collect();
+                line.len() <= played.len() && line.iter().zip(played.iter()).all(|(a, b)| a == b)
+            })
+            .max_by_key(|(moves, _)| moves.len())
+            .map(|&(_, name)| name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Game;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mv in moves {
+            game.make_uci_move(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn names_the_longest_matching_line() {
+        let mut game = Game::new();
+        assert_eq!(game.opening_name(), None);
+        play(&mut game, &["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(game.opening_name(), Some("King's Knight Opening"));
+        play(&mut game, &["b8c6", "f1b5"]);
+        assert_eq!(game.opening_name(), Some("Ruy Lopez"));
+        play(&mut game, &["a7a6"]);
+        assert_eq!(game.opening_name(), Some("Ruy Lopez"));
+    }
+
+    #[test]
+    fn needs_the_standard_start() {
+        let mut later =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 5").unwrap();
+        play(&mut later, &["e2e4"]);
+        assert_eq!(later.opening_name(), None);
+
+        let mut elsewhere =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1").unwrap();
+        play(&mut elsewhere, &["e2e4"]);
+        assert_eq!(elsewhere.opening_name(), None);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{promotion_rank, Game, Move, PieceType};
+
+/// Pieces a pawn may promote to, each of which counts as a move of its own
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+impl Game {
+    /// Counts the positions reached by playing every sequence of `depth` legal moves, for
+    /// checking the move generator against published counts: 20, 400 and 8902 from the starting
+    /// position at depths 1 to 3. Each promotion counts four times, once for every piece the
+    /// pawn can become
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_from(depth)
+    }
+
+    fn perft_from(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for mv in self.legal_moves() {
+            for mv in self.promotion_choices(mv) {
+                let record = self.apply_move(mv);
+                nodes += self.perft_from(depth - 1);
+                self.revert_move(record);
+            }
+        }
+        nodes
+    }
+
+    /// `mv` once for each piece it can promote to if it takes a pawn to the last rank, or just
+    /// `mv` otherwise
+    fn promotion_choices(&self, mv: Move) -> Vec<Move> {
+        let promotes = self.board.square(mv.start).is_some_and(|piece| {
+            piece.piece_type == PieceType::Pawn && mv.end.x == promotion_rank(piece.color)
+        });
+        if !promotes {
+            return vec![mv];
+        }
+        PROMOTIONS
+            .iter()
+            .map(|&promotion| Move {
+                promotion: Some(promotion),
+                ..mv
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts published on the Chess Programming Wiki's perft results page
+    fn assert_counts(fen: &str, counts: &[u64]) {
+        let game = Game::from_fen(fen).unwrap();
+        for (depth, &count) in counts.iter().enumerate() {
+            assert_eq!(
+                game.perft(depth as u32 + 1),
+                count,
+                "depth {} of {}",
+                depth + 1,
+                fen
+            );
+        }
+        assert_eq!(game.perft(0), 1);
+    }
+
+    #[test]
+    fn perft_from_the_start() {
+        assert_counts(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281],
+        );
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        // Castling both ways, pins, en passant and promotions all in one position
+        assert_counts(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        );
+    }
+
+    #[test]
+    fn perft_endgame_with_en_passant_pins() {
+        assert_counts(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        );
+    }
+
+    #[test]
+    fn perft_promotions_and_checks() {
+        assert_counts(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        );
+        assert_counts(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        );
+    }
+
+    #[test]
+    fn perft_quiet_middlegame() {
+        assert_counts(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        );
+    }
+}
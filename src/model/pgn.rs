@@ -0,0 +1,343 @@
+use std::error::Error;
+use std::fmt;
+
+use super::{Color, FenError, Game, GameResult, ParseError};
+
+/// Longest line of movetext, as the PGN standard recommends
+const PGN_LINE_LENGTH: usize = 79;
+
+/// FEN of the standard starting position, which needs no SetUp or FEN tags
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The ways reading a PGN game can fail
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnError {
+    /// A line starting with '[' that is not a tag pair like `[Event "Casual game"]`
+    InvalidTag(String),
+    /// The FEN tag holds a position that cannot be set up
+    InvalidFen(FenError),
+    /// A `{` comment or `(` variation that is never closed, or a closing one never opened
+    Unbalanced(char),
+    /// A move that cannot be played, with the number of the move it was listed under
+    IllegalMove {
+        number: u32,
+        san: String,
+        reason: ParseError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(line) => write!(f, "invalid tag pair '{}'", line),
+            PgnError::InvalidFen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnError::Unbalanced(bracket) => write!(f, "unbalanced '{}'", bracket),
+            PgnError::IllegalMove {
+                number,
+                san,
+                reason,
+            } => write!(f, "move {} '{}': {}", number, san, reason),
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+/// Reads a tag pair such as `[Event "Casual game"]` into its name and value
+fn parse_tag(line: &str) -> Result<(&str, &str), PgnError> {
+    let invalid = || PgnError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((name, value))
+}
+
+/// The movetext with `{}` and `;` comments and `()` variations, which may nest, taken out
+fn strip_comments(movetext: &str) -> Result<String, PgnError> {
+    let mut text = String::new();
+    let mut variation_depth = 0;
+    let mut chars = movetext.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                chars
+                    .by_ref()
+                    .find(|&c| c == '}')
+                    .ok_or(PgnError::Unbalanced('{'))?;
+                text.push(' ');
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+                text.push(' ');
+            }
+            '(' => variation_depth += 1,
+            ')' if variation_depth == 0 => return Err(PgnError::Unbalanced(')')),
+            ')' => {
+                variation_depth -= 1;
+                text.push(' ');
+            }
+            '}' => return Err(PgnError::Unbalanced('}')),
+            _ if variation_depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+    if variation_depth > 0 {
+        return Err(PgnError::Unbalanced('('));
+    }
+    Ok(text)
+}
+
+/// The token PGN uses for a result, "*" while the game is still going on
+fn result_token(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWins) => "1-0",
+        Some(GameResult::BlackWins) => "0-1",
+        Some(GameResult::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+impl Game {
+    /// Writes the game as PGN: the seven tag roster with placeholder values for everything but
+    /// the result, followed by every move played through make_move in algebraic notation. A game
+    /// set up from another position also gets SetUp and FEN tags
+    pub fn to_pgn(&self) -> String {
+        let result = result_token(self.result());
+
+        // Take every move back to find the starting position, then replay them to name each
+        let mut replay = self.clone();
+        let mut moves = Vec::new();
+        while let Some(mv) = replay.unmake_move() {
+            moves.push(mv);
+        }
+        moves.reverse();
+        let start = replay.to_fen();
+
+        let mut pgn = String::new();
+        for (tag, value) in [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+        }
+        if start != START_FEN {
+            pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", start));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        for (index, mv) in moves.into_iter().enumerate() {
+            // Move numbers stay on the same line as the move they number. A game starting with
+            // Black to move opens with "1..." before Black's move
+            let san = replay.move_to_san(mv);
+            if replay.turn == Color::White {
+                tokens.push(format!("{}. {}", replay.fullmove_number, san));
+            } else if index == 0 {
+                tokens.push(format!("{}... {}", replay.fullmove_number, san));
+            } else {
+                tokens.push(san);
+            }
+            replay
+                .make_move(mv)
+                .expect("moves in the history were legal when they were played");
+        }
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > PGN_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Plays through a game written in PGN. Tag pairs are read for a starting position given
+    /// by the SetUp and FEN tags and otherwise skipped, as are comments, variations, numeric
+    /// annotations and the result. Every move must be legal, in algebraic notation
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        let mut fen = None;
+        let mut movetext = String::new();
+        for line in pgn.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                let (name, value) = parse_tag(line)?;
+                if name == "FEN" {
+                    fen = Some(value.to_string());
+                }
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+        let mut game = match fen {
+            Some(fen) => Game::from_fen(&fen).map_err(PgnError::InvalidFen)?,
+            None => Game::new(),
+        };
+
+        for token in strip_comments(&movetext)?.split_whitespace() {
+            // Move numbers may be written against the move, as in "1.e4" or "3...Nf6"
+            let san = match token.rfind('.') {
+                Some(dot) if token.starts_with(|c: char| c.is_ascii_digit()) => &token[dot + 1..],
+                _ => token,
+            };
+            if san.is_empty()
+                || san.starts_with('$')
+                || ["1-0", "0-1", "1/2-1/2", "*"].contains(&san)
+            {
+                continue;
+            }
+            let illegal = |reason| PgnError::IllegalMove {
+                number: game.fullmove_number,
+                san: san.to_string(),
+                reason,
+            };
+            let mv = game.parse_move(san).map_err(illegal)?;
+            game.make_move(mv)
+                .expect("parse_move only finds legal moves");
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Engine, RandomEngine};
+    use super::*;
+
+    fn play(moves: &[&str]) -> Game {
+        let mut game = Game::new();
+        for uci in moves {
+            game.make_uci_move(uci).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn to_pgn_writes_the_roster_and_numbered_moves() {
+        let game = play(&[
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "f3g5", "d7d5", "e4d5", "f6d5", "g5f7",
+            "e8f7", "d1f3", "f7e6", "b1c3",
+        ]);
+        let pgn = game.to_pgn();
+        assert!(
+            pgn.starts_with(
+                "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                 [White \"?\"]\n[Black \"?\"]\n[Result \"*\"]\n\n"
+            ),
+            "{}",
+            pgn
+        );
+        assert!(
+            pgn.ends_with(
+                "\n1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5 d5 5. exd5 Nxd5 6. Nxf7 Kxf7 7. Qf3+ Ke6\n\
+                 8. Nc3 *\n"
+            ),
+            "{}",
+            pgn
+        );
+
+        let pgn = play(&["f2f3", "e7e5", "g2g4", "d8h4"]).to_pgn();
+        assert!(pgn.contains("[Result \"0-1\"]"), "{}", pgn);
+        assert!(pgn.ends_with("\n1. f3 e5 2. g4 Qh4# 0-1\n"), "{}", pgn);
+
+        // A game set up from a position names it and starts from its move number
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12").unwrap();
+        game.make_uci_move("e8d7").unwrap();
+        game.make_uci_move("e2e4").unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n12... Kd7 13. e4 *\n"), "{}", pgn);
+    }
+
+    #[test]
+    fn to_pgn_wraps_long_games() {
+        let engine = RandomEngine::new(3);
+        let mut game = Game::new();
+        while game.result().is_none() && game.history.len() < 120 {
+            let mv = engine.choose_move(&game).unwrap();
+            game.make_move(mv).unwrap();
+        }
+        let pgn = game.to_pgn();
+        assert!(
+            pgn.lines().all(|line| line.len() <= PGN_LINE_LENGTH),
+            "{}",
+            pgn
+        );
+        assert!(pgn.lines().count() > 10, "{}", pgn);
+    }
+
+    #[test]
+    fn from_pgn_replays_a_recorded_game() {
+        let opera = "[Event \"Opera game\"]\n[Site \"Paris\"]\n[Date \"1858.??.??\"]\n\
+                     [Round \"?\"]\n[White \"Morphy\"]\n[Black \"Duke and Count\"]\n\
+                     [Result \"1-0\"]\n\n\
+                     1. e4 e5 2. Nf3 d6 3. d4 Bg4 {a weak move} 4. dxe5 Bxf3 5. Qxf3 dxe5\n\
+                     6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 (9... Qc7 10. O-O-O)\n\
+                     10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6\n\
+                     15. Bxd7+ Nxd7 16. Qb8+ $1 Nxb8 17. Rd8# 1-0\n";
+        let game = Game::from_pgn(opera).unwrap();
+        assert_eq!(
+            game.to_fen(),
+            "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+        );
+        assert_eq!(game.result(), Some(GameResult::WhiteWins));
+        let again = Game::from_pgn(&game.to_pgn()).unwrap();
+        assert_eq!(again.to_fen(), game.to_fen());
+
+        // Numbers written against the moves, a starting position and a rest-of-line comment
+        let game = Game::from_pgn(
+            "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n12...Kd7 13.e4 *",
+        )
+        .unwrap();
+        assert_eq!(game.to_fen(), "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 13");
+        let game = Game::from_pgn("1. e4 ; comment e5\n1... c5").unwrap();
+        assert!(game.to_fen().starts_with("rnbqkbnr/pp1ppppp/8/2p5/4P3"));
+    }
+
+    #[test]
+    fn from_pgn_reports_what_went_wrong() {
+        match Game::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 Qxe4 4. O-O") {
+            Err(PgnError::IllegalMove { number, san, .. }) => {
+                assert_eq!((number, san.as_str()), (3, "Qxe4"))
+            }
+            other => panic!("{:?}", other.map(|game| game.to_fen())),
+        }
+        assert_eq!(
+            Game::from_pgn("1. e4 {unterminated").err(),
+            Some(PgnError::Unbalanced('{'))
+        );
+        assert_eq!(
+            Game::from_pgn("1. e4 (1. d4").err(),
+            Some(PgnError::Unbalanced('('))
+        );
+        assert!(matches!(
+            Game::from_pgn("[Event Casual]\n1. e4"),
+            Err(PgnError::InvalidTag(_))
+        ));
+        assert!(matches!(
+            Game::from_pgn("[FEN \"bad\"]\n"),
+            Err(PgnError::InvalidFen(_))
+        ));
+    }
+}
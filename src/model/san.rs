@@ -0,0 +1,430 @@
+use super::{all_squares, promotion_rank, Game, Move, MoveError, ParseError, PieceType, Position};
+
+/// Letter used for a piece in algebraic notation. Pawns have none
+fn san_letter(piece_type: PieceType) -> Option<char> {
+    match piece_type {
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K'),
+        PieceType::Pawn => None,
+    }
+}
+
+/// Piece named by a letter in algebraic notation. Only upper case letters name pieces, since
+/// a lower case "b" is a file
+pub(super) fn piece_from_san_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+impl Game {
+    /// Writes a legal move for the side to move in standard algebraic notation, such as "Nf3",
+    /// "exd5", "Rae1", "O-O" or "Qh4#"
+    pub fn move_to_san(&self, mv: Move) -> String {
+        self.san_among(mv, &self.legal_moves())
+    }
+
+    /// Explains why a move was refused, naming it the way algebraic notation would, such as
+    /// "Rd4: path blocked". The move need not be legal, so it is written without captures,
+    /// disambiguation or check
+    pub fn describe_rejection(&self, mv: Move, error: &MoveError) -> String {
+        let letter = if mv.start.is_on_board() {
+            self.board.piece_type_at(mv.start).and_then(san_letter)
+        } else {
+            None
+        };
+        let square = if mv.end.is_on_board() {
+            mv.end.to_algebraic()
+        } else {
+            "??".to_string()
+        };
+        match letter {
+            Some(letter) => format!("{}{}: {}", letter, square, error),
+            None => format!("{}: {}", square, error),
+        }
+    }
+
+    /// Reads a move for the side to move written in algebraic notation, such as "e4", "Nf3",
+    /// "exd5", "Rae1", "e8=Q" or "O-O", and finds the legal move it names. Check and
+    /// annotation marks at the end are ignored
+    pub fn parse_move(&self, san: &str) -> Result<Move, ParseError> {
+        let candidates = self.san_candidates(san, self.legal_moves());
+        if let Ok([mv]) = candidates.as_deref() {
+            return Ok(*mv);
+        }
+        if let Ok([_, _, ..]) = candidates.as_deref() {
+            return Err(ParseError::AmbiguousMove(san.to_string()));
+        }
+        // Nothing matched, which is worth explaining when it is the other side's move
+        let other = self.with_other_side_to_move();
+        if other
+            .san_candidates(san, other.legal_moves())
+            .is_ok_and(|moves| !moves.is_empty())
+        {
+            return Err(ParseError::WrongTurn(san.to_string()));
+        }
+        candidates.and(Err(ParseError::NoMatchingMove(san.to_string())))
+    }
+
+    /// The move `san` names for the side to move when legality is set aside, with the reason
+    /// it cannot be played, for explaining why a typed move was refused. When several pieces
+    /// could be meant, one refused for a reason more telling than its shape is preferred.
+    /// `None` if no piece of the side to move could even be meant
+    pub(super) fn refused_san_move(&self, san: &str) -> Option<(Move, MoveError)> {
+        let every_move: Vec<Move> = self
+            .board
+            .pieces(self.turn)
+            .flat_map(|(start, _)| {
+                all_squares().map(move |end| Move {
+                    start,
+                    end,
+                    promotion: None,
+                })
+            })
+            .collect();
+        let refused: Vec<(Move, MoveError)> = self
+            .san_candidates(san, every_move)
+            .ok()?
+            .into_iter()
+            .map(|mv| (mv, self.rejection_reason(mv)))
+            .collect();
+        refused
+            .iter()
+            .find(|(_, error)| *error != MoveError::IllegalMove)
+            .or_else(|| refused.first())
+            .cloned()
+    }
+
+    /// Every move in `legal` that `san` could stand for
+    fn san_candidates(&self, san: &str, legal: Vec<Move>) -> Result<Vec<Move>, ParseError> {
+        let text = san.trim_end_matches(|c| "+#!?".contains(c));
+        let invalid = || ParseError::InvalidMove(san.to_string());
+        let candidates: Vec<Move> = match text {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let kingside = text.len() == 3;
+                legal
+                    .into_iter()
+                    .filter(|&mv| self.castle_side(mv) == Some(kingside))
+                    .collect()
+            }
+            _ => {
+                if !text.is_ascii() {
+                    return Err(invalid());
+                }
+                let (text, promotion) = match text.find('=') {
+                    Some(index) => (&text[..index], Some(&text[index + 1..])),
+                    None => (text, None),
+                };
+                let promotion = match promotion {
+                    Some(letter @ ("Q" | "R" | "B" | "N")) => letter.chars().next(),
+                    Some(_) => return Err(invalid()),
+                    None => None,
+                }
+                .and_then(piece_from_san_letter);
+
+                let piece_type = text
+                    .chars()
+                    .next()
+                    .and_then(piece_from_san_letter)
+                    .unwrap_or(PieceType::Pawn);
+                let rest = if piece_type == PieceType::Pawn {
+                    text
+                } else {
+                    &text[1..]
+                };
+                // Past the piece letter only files, ranks and a capture mark can appear, so
+                // anything else makes the whole token malformed rather than a bad square
+                if rest.len() < 2
+                    || !rest
+                        .chars()
+                        .all(|c| matches!(c, 'a'..='h' | '1'..='8' | 'x'))
+                {
+                    return Err(invalid());
+                }
+                let end = Position::from_algebraic(&rest[rest.len() - 2..])?;
+                let mut hint = &rest[..rest.len() - 2];
+                let capture = hint.ends_with('x');
+                if capture {
+                    hint = &hint[..hint.len() - 1];
+                }
+                // What is left names the file, rank or square the piece comes from
+                let mut file = None;
+                let mut rank = None;
+                for c in hint.chars() {
+                    match c {
+                        'a'..='h' if file.is_none() && rank.is_none() => {
+                            file = Some(c as i8 - b'a' as i8)
+                        }
+                        '1'..='8' if rank.is_none() => rank = Some(c as i8 - b'1' as i8),
+                        _ => return Err(invalid()),
+                    }
+                }
+                // Only a pawn reaching the last rank can promote
+                if promotion.is_some()
+                    && (piece_type != PieceType::Pawn || end.x != promotion_rank(self.turn))
+                {
+                    return Err(ParseError::NoMatchingMove(san.to_string()));
+                }
+                // A pawn named without a file is pushed along the destination's file
+                let from_file = match (piece_type, file) {
+                    (PieceType::Pawn, None) => Some(end.y),
+                    _ => file,
+                };
+                let has_piece = self
+                    .board
+                    .pieces_of(piece_type, self.turn)
+                    .iter()
+                    .any(|pos| {
+                        from_file.is_none_or(|y| pos.y == y) && rank.is_none_or(|x| pos.x == x)
+                    });
+                if !has_piece {
+                    return Err(ParseError::NoSuchPiece(san.to_string()));
+                }
+
+                legal
+                    .into_iter()
+                    .filter(|&mv| {
+                        mv.end == end
+                            && self.board.piece_type_at(mv.start) == Some(piece_type)
+                            && file.is_none_or(|y| mv.start.y == y)
+                            && rank.is_none_or(|x| mv.start.x == x)
+                            && (!capture || self.is_capture(mv))
+                            // A pawn without a file to come from is pushed straight ahead
+                            && (piece_type != PieceType::Pawn
+                                || file.is_some()
+                                || mv.start.y == mv.end.y)
+                    })
+                    .map(|mv| Move { promotion, ..mv })
+                    .collect()
+            }
+        };
+        Ok(candidates)
+    }
+
+    /// Every legal move for the side to move paired with how it is written in algebraic notation
+    pub fn legal_moves_san(&self) -> Vec<(Move, String)> {
+        let legal = self.legal_moves();
+        legal
+            .iter()
+            .map(|&mv| (mv, self.san_among(mv, &legal)))
+            .collect()
+    }
+
+    /// Writes `mv` in algebraic notation, disambiguating against the other moves in `legal`
+    fn san_among(&self, mv: Move, legal: &[Move]) -> String {
+        let mut san = match self.castle_side(mv) {
+            Some(true) => "O-O".to_string(),
+            Some(false) => "O-O-O".to_string(),
+            None => self.san_body(mv, legal),
+        };
+
+        let mut after = self.clone();
+        after.apply_move(mv);
+        if after.is_in_check(after.turn) {
+            san.push(if !after.has_legal_moves() { '#' } else { '+' });
+        }
+        san
+    }
+
+    /// Everything in the notation of a move except castling and the check suffix
+    fn san_body(&self, mv: Move, legal: &[Move]) -> String {
+        let piece_type = self.board.piece_type_at(mv.start);
+        let capture = self.is_capture(mv);
+        let destination = mv.end.to_algebraic();
+        let letter = match piece_type.and_then(san_letter) {
+            Some(letter) => letter,
+            None => {
+                // Pawn captures are named by the file the pawn left
+                let mut san = if capture {
+                    format!("{}x{}", &mv.start.to_algebraic()[..1], destination)
+                } else {
+                    destination
+                };
+                if let Some(color) = self.board.color_at(mv.start) {
+                    if mv.end.x == promotion_rank(color) {
+                        let promoted = mv.promotion.unwrap_or(PieceType::Queen);
+                        san.push('=');
+                        san.extend(san_letter(promoted));
+                    }
+                }
+                return san;
+            }
+        };
+
+        let mut san = letter.to_string();
+        // Other pieces of the same kind that could also reach the destination
+        let rivals: Vec<&Move> = legal
+            .iter()
+            .filter(|other| {
+                other.end == mv.end
+                    && other.start != mv.start
+                    && self.board.piece_type_at(other.start) == piece_type
+            })
+            .collect();
+        if !rivals.is_empty() {
+            let start = mv.start.to_algebraic();
+            if rivals.iter().all(|other| other.start.y != mv.start.y) {
+                san.push_str(&start[..1]);
+            } else if rivals.iter().all(|other| other.start.x != mv.start.x) {
+                san.push_str(&start[1..]);
+            } else {
+                san.push_str(&start);
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san + &destination
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn mv(from: &str, to: &str) -> Move {
+        Move {
+            start: Position::from_algebraic(from).unwrap(),
+            end: Position::from_algebraic(to).unwrap(),
+            promotion: None,
+        }
+    }
+
+    #[test]
+    fn parse_errors_say_what_went_wrong() {
+        let game = Game::new();
+        assert_eq!(
+            game.parse_move("Zz9"),
+            Err(ParseError::InvalidMove("Zz9".to_string()))
+        );
+        assert_eq!(
+            game.parse_move("Nz3"),
+            Err(ParseError::InvalidMove("Nz3".to_string()))
+        );
+        assert_eq!(
+            game.parse_move("e5"),
+            Err(ParseError::WrongTurn("e5".to_string()))
+        );
+        assert_eq!(
+            game.parse_move("Nf6"),
+            Err(ParseError::WrongTurn("Nf6".to_string()))
+        );
+        assert_eq!(game.parse_move("e4"), Ok(mv("e2", "e4")));
+
+        let knights = Game::from_fen("4k3/8/8/8/8/2N3N1/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            knights.parse_move("Ne4"),
+            Err(ParseError::AmbiguousMove("Ne4".to_string()))
+        );
+        assert_eq!(knights.parse_move("Nce4"), Ok(mv("c3", "e4")));
+        assert_eq!(
+            knights.parse_move("Bc4"),
+            Err(ParseError::NoSuchPiece("Bc4".to_string()))
+        );
+    }
+
+    #[test]
+    fn refused_moves_are_explained_in_either_notation() {
+        let mut game = Game::new();
+        for input in ["Ra4", "a1a4"] {
+            let message = game.play_typed_move(input).unwrap_err();
+            assert!(message.contains("Ra4"), "{}", message);
+            assert!(message.contains("blocked"), "{}", message);
+        }
+        let message = game.play_typed_move("Nd4").unwrap_err();
+        assert!(message.starts_with("Nd4"), "{}", message);
+        assert_eq!(game.history.len(), 0);
+        assert!(game.play_typed_move("Nc3").is_ok());
+    }
+
+    #[test]
+    fn san_for_every_legal_move() {
+        let game = Game::new();
+        let names: Vec<String> = game
+            .legal_moves_san()
+            .into_iter()
+            .map(|(_, san)| san)
+            .collect();
+        assert_eq!(names.len(), 20);
+        for name in ["Na3", "Nc3", "Nf3", "Nh3", "e4", "a3"] {
+            assert!(names.contains(&name.to_string()), "{}", name);
+        }
+
+        let mut game = Game::new();
+        for uci in ["f2f3", "e7e5", "g2g4"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(game.move_to_san(mv("d8", "h4")), "Qh4#");
+
+        let mut game = Game::new();
+        for uci in ["e2e4", "d7d5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(game.move_to_san(mv("e4", "d5")), "exd5");
+        assert_eq!(game.move_to_san(mv("f1", "b5")), "Bb5+");
+
+        // Both knights reach d2, so each names its file
+        let mut game = Game::new();
+        for uci in ["g1f3", "a7a6", "d2d3", "a6a5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(game.move_to_san(mv("b1", "d2")), "Nbd2");
+        assert_eq!(game.move_to_san(mv("f3", "d2")), "Nfd2");
+
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.move_to_san(mv("e1", "g1")), "O-O");
+        assert_eq!(game.move_to_san(mv("e1", "c1")), "O-O-O");
+    }
+
+    #[test]
+    fn parse_move_reads_algebraic_notation() {
+        let game = Game::new();
+        assert_eq!(game.parse_move("e4"), Ok(mv("e2", "e4")));
+        assert_eq!(game.parse_move("Nf3"), Ok(mv("g1", "f3")));
+        assert_eq!(game.parse_move("Ng1f3+"), Ok(mv("g1", "f3")));
+        assert_eq!(game.parse_move("e2e4"), Ok(mv("e2", "e4")));
+
+        let mut game = Game::new();
+        for uci in ["e2e4", "d7d5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert_eq!(game.parse_move("exd5"), Ok(mv("e4", "d5")));
+        assert_eq!(
+            game.parse_move("Nxf3"),
+            Err(ParseError::NoMatchingMove("Nxf3".to_string()))
+        );
+
+        let rooks = Game::from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+        assert_eq!(
+            rooks.parse_move("Rd1"),
+            Err(ParseError::AmbiguousMove("Rd1".to_string()))
+        );
+        assert_eq!(rooks.parse_move("Rad1"), Ok(mv("a1", "d1")));
+        assert_eq!(rooks.parse_move("Rfd1"), Ok(mv("f1", "d1")));
+
+        let castles = Game::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert_eq!(castles.parse_move("O-O"), Ok(mv("e1", "g1")));
+        assert_eq!(castles.parse_move("O-O-O"), Ok(mv("e1", "c1")));
+
+        let promotion = Game::from_fen("7k/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            promotion.parse_move("a8=N"),
+            Ok(Move {
+                promotion: Some(PieceType::Knight),
+                ..mv("a7", "a8")
+            })
+        );
+        assert!(matches!(
+            promotion.parse_move("a8=K"),
+            Err(ParseError::InvalidMove(_))
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use std::time::{Duration, Instant};
+
+use super::{Color, Game, Move};
+
+/// Score of being checkmated at the root. Mates found deeper in the tree score slightly less so
+/// the search prefers the quickest mate and the slowest loss
+pub(super) const MATE_SCORE: i32 = 100_000;
+
+/// How often, in nodes, the search looks at the clock
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// When a search has to stop. Iterations run one depth at a time and the search ends after
+/// `depth`, or as soon as the time or node budget runs out, whichever comes first
+#[derive(Copy, Clone, Debug)]
+pub struct SearchLimits {
+    /// Deepest iteration to search, in plies
+    pub depth: u32,
+    /// Wall clock time the search may use
+    pub time: Option<Duration>,
+    /// Most positions the search may visit
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    /// Search to a fixed depth with no time or node limit
+    pub fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth,
+            time: None,
+            nodes: None,
+        }
+    }
+}
+
+/// Bounds of the search window, just outside any score a position can get
+const SCORE_BOUND: i32 = MATE_SCORE + 1;
+
+/// Bookkeeping for a single search, shared by best_move, analyze_line and MinimaxEngine
+pub(super) struct Search {
+    limits: SearchLimits,
+    started: Instant,
+    nodes: u64,
+    /// Set once a limit is hit, after which every score is meaningless
+    stopped: bool,
+    /// Skip lines that cannot change the choice at the root
+    pub(super) pruning: bool,
+    /// Keep searching captures past the last ply until the position is quiet
+    pub(super) quiescence: bool,
+}
+
+impl Search {
+    /// A pruning, quiescent search that stops at `limits`
+    pub(super) fn new(limits: SearchLimits) -> Search {
+        Search {
+            limits,
+            started: Instant::now(),
+            nodes: 0,
+            stopped: false,
+            pruning: true,
+            quiescence: true,
+        }
+    }
+
+    /// How many positions the search has visited
+    pub(super) fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Counts a node and reports whether the search must stop
+    fn out_of_budget(&mut self) -> bool {
+        self.nodes += 1;
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes {
+                self.stopped = true;
+            }
+        }
+        if let Some(time) = self.limits.time {
+            if self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.started.elapsed() >= time {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    /// Deepens one ply at a time up to the depth limit and returns the best move for the side
+    /// to move with its score in centipawns. If a limit interrupts an iteration, the move from
+    /// the last completed iteration is returned, and if not even the first finished, the first
+    /// legal move is, scored by Game::evaluate. Of moves that score the same, the first one
+    /// generated is picked. Returns `None` only when there are no legal moves
+    pub(super) fn run(&mut self, game: &Game) -> Option<(Move, i32)> {
+        let moves = game.legal_moves();
+        let mut best = (*moves.first()?, game.evaluate());
+        let mut game = game.clone();
+        for depth in 1..=self.limits.depth {
+            match self.root(&mut game, &moves, depth) {
+                Some(found) => best = found,
+                None => break,
+            }
+        }
+        Some(best)
+    }
+
+    /// Best of `moves` searched `depth` plies deep, or `None` if a limit stopped the search
+    fn root(&mut self, game: &mut Game, moves: &[Move], depth: u32) -> Option<(Move, i32)> {
+        let mut best = None;
+        let mut alpha = -SCORE_BOUND;
+        for &mv in moves {
+            let record = game.apply_move(mv);
+            let score = -self.negamax(game, depth - 1, 1, -SCORE_BOUND, -alpha);
+            game.revert_move(record);
+            if self.stopped {
+                return None;
+            }
+            // A move that only ties the best so far comes back as a bound, never above it
+            if score > alpha {
+                alpha = score;
+                best = Some((mv, score));
+            }
+        }
+        best
+    }
+
+    /// Negamax score of `game` searched `depth` plies deep, from the side to move's point of
+    /// view. `ply` is the distance from the root, so nearer mates score higher. Scores outside
+    /// `alpha..beta` are only bounds when pruning
+    fn negamax(&mut self, game: &mut Game, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_budget() {
+            return 0;
+        }
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            return if game.is_in_check(game.turn) {
+                -MATE_SCORE + ply
+            } else {
+                0
+            };
+        }
+        if depth == 0 {
+            return if self.quiescence {
+                self.quiesce(game, alpha, beta)
+            } else {
+                game.evaluate()
+            };
+        }
+        let mut best = -MATE_SCORE;
+        for mv in moves {
+            let record = game.apply_move(mv);
+            let score = -self.negamax(game, depth - 1, ply + 1, -beta, -alpha);
+            game.revert_move(record);
+            if self.stopped {
+                return 0;
+            }
+            best = best.max(score);
+            alpha = alpha.max(best);
+            // The opponent already has a better line elsewhere, so they never allow this one
+            if self.pruning && alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Score of `game` once the captures available have played out. The side to move may stop
+    /// capturing whenever the position as it stands is better for it, so only captures
+    /// that gain something change the score. Checks and mates are not looked for. Captures are
+    /// always pruned, even without pruning, since scores outside `alpha..beta` only ever come
+    /// back as bounds and leave the choice at the root unchanged
+    fn quiesce(&mut self, game: &mut Game, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_budget() {
+            return 0;
+        }
+        let mut best = game.evaluate();
+        alpha = alpha.max(best);
+        if alpha >= beta {
+            return best;
+        }
+        // Taking the most valuable piece with the least valuable one first finds the
+        // cutoffs soonest
+        let mut captures: Vec<(Move, i32)> = game
+            .legal_captures()
+            .into_iter()
+            .filter_map(|mv| {
+                let (victim, _) = game.capture_target(mv)?;
+                let attacker = game.board().piece_at(mv.start)?.kind();
+                Some((mv, attacker.value() - victim.value() * 10))
+            })
+            .collect();
+        captures.sort_by_key(|&(_, order)| order);
+        for (mv, _) in captures {
+            let record = game.apply_move(mv);
+            let score = -self.quiesce(game, -beta, -alpha);
+            game.revert_move(record);
+            if self.stopped {
+                return 0;
+            }
+            best = best.max(score);
+            alpha = alpha.max(best);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl Game {
+    /// Searches for the best move for the side to move within `limits`, deepening one ply at a
+    /// time. If a limit interrupts an iteration, the move from the last completed iteration is
+    /// returned, and if not even the first finished, the first legal move is. Returns `None`
+    /// only when there are no legal moves
+    pub fn best_move(&self, limits: SearchLimits) -> Option<Move> {
+        Search::new(limits).run(self).map(|(mv, _)| mv)
+    }
+
+    /// Walks through the moves played so far, pairing each with the engine's score of the
+    /// position it was played in, searched `depth` plies deep. Scores are from White's point of
+    /// view so they can be read down the line as one graph. The game is left as it was
+    pub fn analyze_line(&mut self, depth: u32) -> Vec<(Move, i32)> {
+        let claimed_draw = self.claimed_draw;
+        let mut played = Vec::new();
+        while let Some(mv) = self.unmake_move() {
+            played.push(mv);
+        }
+        let mut line = Vec::new();
+        for mv in played.into_iter().rev() {
+            let score = Search::new(SearchLimits::depth(depth)).negamax(
+                &mut self.clone(),
+                depth,
+                0,
+                -SCORE_BOUND,
+                SCORE_BOUND,
+            );
+            line.push((
+                mv,
+                match self.turn {
+                    Color::Black => -score,
+                    _ => score,
+                },
+            ));
+            self.make_move(mv)
+                .expect("a move taken back is legal to play again");
+        }
+        self.claimed_draw = claimed_draw;
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_move_stops_at_its_limits() {
+        let mut game = Game::new();
+        game.make_uci_move("e2e4").unwrap();
+        game.make_uci_move("d7d5").unwrap();
+
+        let mut search = Search::new(SearchLimits {
+            nodes: Some(50),
+            ..SearchLimits::depth(10)
+        });
+        let (mv, _) = search.run(&game).unwrap();
+        assert!(game.legal_moves().contains(&mv));
+        assert!(search.nodes() <= 50, "{}", search.nodes());
+
+        let started = Instant::now();
+        let mv = game
+            .best_move(SearchLimits {
+                time: Some(Duration::from_millis(100)),
+                ..SearchLimits::depth(20)
+            })
+            .unwrap();
+        assert!(game.legal_moves().contains(&mv));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn best_move_finds_mate_and_handles_no_moves() {
+        let mut game = Game::new();
+        for uci in ["f2f3", "e7e5", "g2g4"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        let mate = game.best_move(SearchLimits::depth(2)).unwrap();
+        assert_eq!(mate.to_uci(), "d8h4");
+
+        game.make_move(mate).unwrap();
+        assert_eq!(game.best_move(SearchLimits::depth(2)), None);
+    }
+
+    #[test]
+    fn analyze_line_scores_every_move_from_whites_side() {
+        let mut game = Game::new();
+        for uci in ["e2e4", "d7d5", "e4d5", "d8d5"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        let fen = game.to_fen();
+        let line = game.analyze_line(1);
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[2].0.to_uci(), "e4d5");
+        assert!(
+            line.iter().all(|&(_, score)| score.abs() < 1000),
+            "{:?}",
+            line
+        );
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.history.len(), 4);
+
+        // Black to move before Qh4 mates, so the score there is lost for White
+        let mut game = Game::new();
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        let line = game.analyze_line(1);
+        assert!(line[3].1 <= -MATE_SCORE + 10, "{:?}", line);
+    }
+}
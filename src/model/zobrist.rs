@@ -0,0 +1,215 @@
+use std::sync::OnceLock;
+
+use super::{CastlingRights, Color, Game, Piece, PieceType, Position, BOARD_DIMENSIONS};
+use crate::rng::{Rng, XorShiftRng};
+
+/// Fixed seed so hashes are identical between runs
+const ZOBRIST_SEED: u64 = 0x5EED_0F2E_6A11_C5E4;
+
+/// Odd multiplier used to mix each move into a game hash, from the golden ratio
+const GAME_HASH_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Random keys XORed together to hash a position
+struct ZobristKeys {
+    /// Indexed by color, piece type and square (rank * 8 + file)
+    pieces: [[[u64; BOARD_DIMENSIONS * BOARD_DIMENSIONS]; 6]; 2],
+    black_to_move: u64,
+    /// Indexed by the file of the en passant square
+    en_passant_file: [u64; BOARD_DIMENSIONS],
+    /// White kingside, White queenside, Black kingside and Black queenside, in that order
+    castling: [u64; 4],
+}
+
+fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = XorShiftRng::new(ZOBRIST_SEED);
+        let mut keys = ZobristKeys {
+            pieces: [[[0; BOARD_DIMENSIONS * BOARD_DIMENSIONS]; 6]; 2],
+            black_to_move: rng.next_u64(),
+            en_passant_file: [0; BOARD_DIMENSIONS],
+            castling: [0; 4],
+        };
+        for color in keys.pieces.iter_mut() {
+            for piece_type in color.iter_mut() {
+                for square in piece_type.iter_mut() {
+                    *square = rng.next_u64();
+                }
+            }
+        }
+        for file in keys.en_passant_file.iter_mut() {
+            *file = rng.next_u64();
+        }
+        for right in keys.castling.iter_mut() {
+            *right = rng.next_u64();
+        }
+        keys
+    })
+}
+
+fn piece_index(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    }
+}
+
+/// Key for `piece` standing on `pos`
+pub(super) fn piece_key(piece: Piece, pos: Position) -> u64 {
+    let color = match piece.color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    keys().pieces[color][piece_index(piece.piece_type)]
+        [pos.x as usize * BOARD_DIMENSIONS + pos.y as usize]
+}
+
+/// Key toggled whenever the side to move changes
+pub(super) fn side_key() -> u64 {
+    keys().black_to_move
+}
+
+/// Key for the file of the en passant square, or 0 when there is none
+pub(super) fn en_passant_key(en_passant: Option<Position>) -> u64 {
+    en_passant.map_or(0, |ep| keys().en_passant_file[ep.y as usize])
+}
+
+/// Keys of every castling right still held, XORed together
+pub(super) fn castling_key(rights: CastlingRights) -> u64 {
+    let held = [
+        rights.white_king,
+        rights.white_queen,
+        rights.black_king,
+        rights.black_queen,
+    ];
+    held.iter()
+        .zip(keys().castling.iter())
+        .filter(|(&held, _)| held)
+        .fold(0, |hash, (_, key)| hash ^ key)
+}
+
+impl Game {
+    /// Zobrist hash of the position: piece placement, side to move, castling rights and en
+    /// passant file. Equal positions always hash equal no matter how they were reached. The hash
+    /// is kept up to date as moves are made and taken back, so this costs nothing
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Hashes the position from scratch, which the incrementally updated hash must always match
+    pub(super) fn full_zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (pos, piece) in self.board.iter_pieces() {
+            hash ^= piece_key(piece, pos);
+        }
+        if self.turn == Color::Black {
+            hash ^= side_key();
+        }
+        hash ^ castling_key(self.castling) ^ en_passant_key(self.en_passant)
+    }
+
+    /// Recomputes the stored hash and starts the repetition history from this position, for
+    /// constructors that set the board up directly instead of playing moves
+    pub(super) fn start_hashing(&mut self) {
+        self.hash = self.full_zobrist_hash();
+        self.position_hashes = vec![self.hash];
+    }
+
+    /// Hash of the whole game: the starting position and every move played through make_move,
+    /// in order, including what each promotion became. Unlike the Zobrist hash, two games
+    /// reaching the same position by different move orders hash differently
+    pub fn game_hash(&self) -> u64 {
+        let start = self.position_hashes.first().copied().unwrap_or(self.hash);
+        self.history.iter().fold(start, |hash, record| {
+            let from =
+                record.mv.start.x as u64 * BOARD_DIMENSIONS as u64 + record.mv.start.y as u64;
+            let to = record.mv.end.x as u64 * BOARD_DIMENSIONS as u64 + record.mv.end.y as u64;
+            // 0 for a move that promotes nothing, so e8=Q and e8=N hash differently
+            let promotion = record
+                .mv
+                .promotion
+                .map_or(0, |piece_type| piece_index(piece_type) as u64 + 1);
+            // Multiplying after each move makes the result depend on the order of the moves
+            (hash.rotate_left(7) ^ (promotion << 12 | from << 6 | to))
+                .wrapping_mul(GAME_HASH_MULTIPLIER)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Game;
+    use crate::rng::{Rng, XorShiftRng};
+
+    #[test]
+    fn game_hash_tells_promotions_apart() {
+        let fen = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+        let mut queen = Game::from_fen(fen).unwrap();
+        queen.make_uci_move("e7e8q").unwrap();
+        let mut knight = Game::from_fen(fen).unwrap();
+        knight.make_uci_move("e7e8n").unwrap();
+        assert_ne!(queen.game_hash(), knight.game_hash());
+        assert_ne!(queen.zobrist_hash(), knight.zobrist_hash());
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_recompute_every_ply() {
+        let mut game = Game::new();
+        // Castles, en passant and a capture, then random moves for everything else
+        for uci in [
+            "e2e4", "a7a6", "e4e5", "d7d5", "e5d6", "c7d6", "g1f3", "b8c6",
+        ] {
+            game.make_uci_move(uci).unwrap();
+            assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+        }
+        game.make_uci_move("f1e2").unwrap();
+        game.make_uci_move("c8g4").unwrap();
+        game.make_uci_move("e1g1").unwrap();
+        assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+        let mut rng = XorShiftRng::new(241);
+        for _ in 0..200 {
+            let moves = game.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            game.make_move(moves[rng.below(moves.len())]).unwrap();
+            assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+        }
+        while game.unmake_move().is_some() {
+            assert_eq!(game.zobrist_hash(), game.full_zobrist_hash());
+        }
+        assert_eq!(game.zobrist_hash(), Game::new().zobrist_hash());
+    }
+
+    #[test]
+    fn transposed_move_orders_hash_equal() {
+        let play = |moves: &[&str]| {
+            let mut game = Game::new();
+            for uci in moves {
+                game.make_uci_move(uci).unwrap();
+            }
+            game
+        };
+        let a = play(&["g1f3", "g8f6", "b1c3"]);
+        let b = play(&["b1c3", "g8f6", "g1f3"]);
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_ne!(a.game_hash(), b.game_hash());
+
+        let start = Game::new();
+        let pushed = play(&["e2e3"]);
+        assert_ne!(start.zobrist_hash(), pushed.zobrist_hash());
+        // Only the side to move differs
+        let waiting =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(start.zobrist_hash(), waiting.zobrist_hash());
+        // Only the en passant square differs
+        let double = play(&["e2e4"]);
+        let same_placement =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(double.zobrist_hash(), same_placement.zobrist_hash());
+    }
+}
@@ -0,0 +1,64 @@
+/// Source of random numbers for anything that needs them, such as fuzzing or random engines
+pub trait Rng {
+    /// The next 64 random bits
+    fn next_u64(&mut self) -> u64;
+
+    /// A number in `0..bound`. `bound` must not be zero
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Small, fast xorshift64* generator. The same seed always produces the same sequence, which
+/// keeps fuzzing runs reproducible
+#[derive(Clone)]
+pub struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> XorShiftRng {
+        // An all zero state would only ever produce zeroes
+        let state = if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        };
+        XorShiftRng { state }
+    }
+}
+
+impl Rng for XorShiftRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = XorShiftRng::new(42);
+        let mut b = XorShiftRng::new(42);
+        let first: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+        assert!(first.iter().all(|&n| n == b.next_u64()));
+        assert_ne!(first[0], XorShiftRng::new(43).next_u64());
+        // A zero seed still produces numbers
+        assert_ne!(XorShiftRng::new(0).next_u64(), 0);
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = XorShiftRng::new(7);
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            seen[rng.below(6)] = true;
+        }
+        assert!(seen.iter().all(|&hit| hit));
+    }
+}
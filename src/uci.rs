@@ -0,0 +1,177 @@
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use crate::model::{Color, Engine, Game, MinimaxEngine, SearchLimits};
+
+/// How many plies `go` searches when it is given neither a depth nor a time or node budget
+const DEFAULT_DEPTH: u32 = 3;
+
+/// Deepest `go` searches when a time or node budget is what stops it
+const MAX_DEPTH: u32 = 64;
+
+/// Share of the remaining clock time spent on one move when `go` only gives the clocks
+const MOVES_TO_GO: u32 = 30;
+
+/// Speaks the Universal Chess Interface so graphical interfaces can run the engine, reading
+/// commands from `input` and writing the replies to `output` until `quit` or the end of the
+/// input. Only the commands needed to play a game are understood: `uci`, `isready`,
+/// `ucinewgame`, `position`, `go` and `quit`. Anything else is ignored, as the protocol asks
+pub fn run(input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut game = Game::new();
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("uci") => {
+                writeln!(output, "id name Regalis")?;
+                writeln!(output, "uciok")?;
+            }
+            Some("isready") => writeln!(output, "readyok")?,
+            Some("ucinewgame") => game = Game::new(),
+            Some("position") => {
+                let words: Vec<&str> = words.collect();
+                match set_up_position(&words) {
+                    Ok(position) => game = position,
+                    Err(message) => writeln!(output, "info string {}", message)?,
+                }
+            }
+            Some("go") => {
+                let words: Vec<&str> = words.collect();
+                let limits = search_limits(&words, game.turn());
+                // "0000" is the null move UCI uses when there is nothing to play
+                let best = MinimaxEngine::with_limits(limits)
+                    .choose_move(&game)
+                    .map_or("0000".to_string(), |mv| mv.to_uci());
+                writeln!(output, "bestmove {}", best)?;
+            }
+            Some("quit") => break,
+            _ => {}
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// The limits set by the words of a `go` command after "go": `depth`, `nodes`, `movetime`, and
+/// the clocks `wtime`, `btime`, `winc` and `binc` in milliseconds. Given only the clocks, a
+/// move may use a thirtieth of the mover's remaining time plus half its increment. Without a
+/// depth, the search deepens until a time or node budget stops it, or searches DEFAULT_DEPTH
+/// plies when there is none
+fn search_limits(words: &[&str], turn: Color) -> SearchLimits {
+    let value = |name: &str| -> Option<u64> {
+        words
+            .iter()
+            .position(|&word| word == name)
+            .and_then(|index| words.get(index + 1))
+            .and_then(|value| value.parse().ok())
+    };
+    let (time, increment) = match turn {
+        Color::Black => ("btime", "binc"),
+        _ => ("wtime", "winc"),
+    };
+    let time = value("movetime").map(Duration::from_millis).or_else(|| {
+        value(time).map(|left| {
+            let left = Duration::from_millis(left);
+            let increment = Duration::from_millis(value(increment).unwrap_or(0));
+            (left / MOVES_TO_GO + increment / 2).min(left)
+        })
+    });
+    let nodes = value("nodes");
+    let budgeted = time.is_some() || nodes.is_some();
+    SearchLimits {
+        depth: value("depth").map_or(if budgeted { MAX_DEPTH } else { DEFAULT_DEPTH }, |depth| {
+            depth as u32
+        }),
+        time,
+        nodes,
+    }
+}
+
+/// The game described by the words of a `position` command after "position": "startpos" or
+/// "fen" followed by the six FEN fields, then optionally "moves" and moves in coordinate
+/// notation. The position is only used if every move in it is legal
+fn set_up_position(words: &[&str]) -> Result<Game, String> {
+    let moves_at = words
+        .iter()
+        .position(|&word| word == "moves")
+        .unwrap_or(words.len());
+    let mut game = match words.first() {
+        Some(&"startpos") => Game::new(),
+        Some(&"fen") => {
+            Game::from_fen(&words[1..moves_at].join(" ")).map_err(|err| err.to_string())?
+        }
+        _ => return Err("position needs startpos or fen".to_string()),
+    };
+    for uci in words.iter().skip(moves_at + 1) {
+        game.make_uci_move(uci)
+            .map_err(|err| format!("{}: {}", uci, err))?;
+    }
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(input: &str) -> String {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn go_reads_every_limit() {
+        let limits = search_limits(&["depth", "5"], Color::White);
+        assert_eq!((limits.depth, limits.time, limits.nodes), (5, None, None));
+        let limits = search_limits(&[], Color::White);
+        assert_eq!(limits.depth, DEFAULT_DEPTH);
+
+        let limits = search_limits(&["nodes", "2000"], Color::White);
+        assert_eq!((limits.depth, limits.nodes), (MAX_DEPTH, Some(2000)));
+        let limits = search_limits(&["movetime", "250"], Color::Black);
+        assert_eq!(limits.time, Some(Duration::from_millis(250)));
+
+        let clocks = [
+            "wtime", "60000", "btime", "3000", "winc", "2000", "binc", "0",
+        ];
+        let limits = search_limits(&clocks, Color::White);
+        assert_eq!(limits.time, Some(Duration::from_millis(3000)));
+        let limits = search_limits(&clocks, Color::Black);
+        assert_eq!(limits.time, Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn go_answers_within_a_budget() {
+        let output = reply("position startpos moves e2e4\ngo nodes 500\ngo movetime 50\nquit\n");
+        let answers: Vec<&str> = output.lines().collect();
+        assert_eq!(answers.len(), 2, "{}", output);
+        assert!(answers.iter().all(|line| line.starts_with("bestmove ")));
+        assert_ne!(answers[0], "bestmove 0000");
+    }
+
+    #[test]
+    fn scripted_session_answers_with_a_legal_move() {
+        let output = reply(
+            "uci\nisready\nucinewgame\nposition startpos moves e2e4 e7e5 g1f3\n\
+             go depth 2\nquit\ngo depth 2\n",
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[..3], ["id name Regalis", "uciok", "readyok"]);
+        assert_eq!(lines.len(), 4, "{}", output);
+        let best = lines[3].strip_prefix("bestmove ").unwrap();
+        let mut game = Game::new();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            game.make_uci_move(uci).unwrap();
+        }
+        assert!(game.make_uci_move(best).is_ok(), "{}", best);
+
+        let output = reply(
+            "position fen rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3\n\
+             go\nposition fen 8/8 w\nposition startpos moves e2e5\n",
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "bestmove 0000");
+        assert!(lines[1].starts_with("info string "), "{}", output);
+        assert!(lines[2].starts_with("info string e2e5"), "{}", output);
+    }
+}